@@ -1,69 +1,187 @@
 use std::{collections::HashMap, io::BufRead};
 
-use itertools::Itertools;
+pub fn what_is_the_total_distance_between_lists(input: &mut dyn BufRead) -> Result<u64, ParseError> {
+    let mut columns = parse_list_pair(input)?;
+    columns.iter_mut().for_each(|c| c.sort_unstable());
 
-pub fn what_is_the_total_distance_between_lists(input: &mut dyn BufRead) -> u32 {
-    let (mut left, mut right) = parse_input(read_input(input));
-    left.sort();
-    right.sort();
+    Ok(total_distance(&columns[0], &columns[1]))
+}
+
+pub fn what_is_lists_similarity_score(input: &mut dyn BufRead) -> Result<u64, ParseError> {
+    let columns = parse_list_pair(input)?;
 
-    return left.iter().zip(right)
-        .map(|(l, r)| l.abs_diff(r))
-        .sum();
+    Ok(similarity_score(&columns[0], &columns[1]))
 }
 
-pub fn what_is_lists_similarity_score(input: &mut dyn BufRead) -> u32 {
-    let (left, right) = parse_input(read_input(input));
-    let right_counts: HashMap<u32, usize> = right.iter()
-        .into_group_map_by(|&n| n)
-        .into_iter()
-        .map(|(n, v)| (*n, v.len()))
-        .collect();
-
-    return left.iter()
-        .map(|n| n * right_counts.get(n).map(|x| *x as u32).unwrap_or(0))
-        .sum();
+pub fn distance_matrix(input: &mut dyn BufRead) -> Result<Vec<Vec<u64>>, ParseError> {
+    let mut columns = parse_columns(input)?;
+    columns.iter_mut().for_each(|c| c.sort_unstable());
+
+    Ok(columns.iter()
+        .map(|a| columns.iter().map(|b| total_distance(a, b)).collect())
+        .collect())
 }
 
-fn read_input(input: &mut dyn BufRead) -> Vec<String> {
-    return input.lines().map(|line| line.unwrap()).collect::<Vec<String>>();
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    InvalidNumber { line: usize, value: String },
+    ColumnCount { line: usize, expected: usize, found: usize },
+    TooFewColumns { expected: usize, found: usize },
+    Io(String),
 }
 
-fn parse_input(input: Vec<String>) -> (Vec<u32>, Vec<u32>) {
-    let re = regex::Regex::new(r"\s+").unwrap();
+pub fn parse_columns(input: &mut dyn BufRead) -> Result<Vec<Vec<u64>>, ParseError> {
+    let mut columns: Vec<Vec<u64>> = Vec::new();
+    let mut line = String::new();
+    let mut line_number = 0;
+
+    loop {
+        line.clear();
+        if input.read_line(&mut line).map_err(|e| ParseError::Io(e.to_string()))? == 0 {
+            break;
+        }
+        line_number += 1;
 
-    let parsed_lines = input.iter()
-        .map(|s| re.splitn(s, 2).collect_tuple().unwrap())
-        .map(|(l, r)| (l.parse::<u32>().unwrap(), r.parse::<u32>().unwrap()))
-        .collect::<Vec<(u32, u32)>>();
+        let first = columns.is_empty();
+        let mut found = 0;
+        for value in line.split_whitespace() {
+            let n = value.parse::<u64>().map_err(|_| ParseError::InvalidNumber {
+                line: line_number,
+                value: value.to_string(),
+            })?;
 
-    return (
-        parsed_lines.iter().map(|e| e.0).collect::<Vec<u32>>(),
-        parsed_lines.iter().map(|e| e.1).collect::<Vec<u32>>()
-    );
+            if first {
+                columns.push(Vec::new());
+            }
+            if let Some(column) = columns.get_mut(found) {
+                column.push(n);
+            }
+            found += 1;
+        }
+
+        if found > 0 && found != columns.len() {
+            return Err(ParseError::ColumnCount { line: line_number, expected: columns.len(), found });
+        }
+    }
+
+    Ok(columns)
+}
+
+fn parse_list_pair(input: &mut dyn BufRead) -> Result<Vec<Vec<u64>>, ParseError> {
+    let columns = parse_columns(input)?;
+
+    if columns.len() < 2 {
+        Err(ParseError::TooFewColumns { expected: 2, found: columns.len() })
+    } else {
+        Ok(columns)
+    }
+}
+
+fn total_distance(sorted_left: &[u64], sorted_right: &[u64]) -> u64 {
+    sorted_left.iter().zip(sorted_right)
+        .map(|(l, r)| l.abs_diff(*r))
+        .sum()
+}
+
+fn similarity_score(left: &[u64], right: &[u64]) -> u64 {
+    let mut right_counts: HashMap<u64, u64> = HashMap::new();
+    for n in right {
+        *right_counts.entry(*n).or_insert(0) += 1;
+    }
+
+    left.iter()
+        .map(|n| n * right_counts.get(n).copied().unwrap_or(0))
+        .sum()
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{day01::{what_is_lists_similarity_score, what_is_the_total_distance_between_lists}, read};
+    use std::io::BufReader;
+
+    use crate::{
+        day01::{
+            distance_matrix, parse_columns, what_is_lists_similarity_score, what_is_the_total_distance_between_lists,
+            ParseError,
+        },
+        read,
+    };
 
     #[test]
     fn sample_part1_input() {
-        assert_eq!(what_is_the_total_distance_between_lists(&mut read("./src/day01/sample.input")), 11);
+        assert_eq!(what_is_the_total_distance_between_lists(&mut read("./src/day01/sample.input")), Ok(11));
     }
 
     #[test]
     fn part1_input() {
-        assert_eq!(what_is_the_total_distance_between_lists(&mut read("./src/day01/my.input")), 1579939);
+        assert_eq!(what_is_the_total_distance_between_lists(&mut read("./src/day01/my.input")), Ok(1579939));
     }
 
     #[test]
     fn sample_part2_input() {
-        assert_eq!(what_is_lists_similarity_score(&mut read("./src/day01/sample.input")), 31);
+        assert_eq!(what_is_lists_similarity_score(&mut read("./src/day01/sample.input")), Ok(31));
     }
 
     #[test]
     fn part2_input() {
-        assert_eq!(what_is_lists_similarity_score(&mut read("./src/day01/my.input")), 20351745);
+        assert_eq!(what_is_lists_similarity_score(&mut read("./src/day01/my.input")), Ok(20351745));
+    }
+
+    #[test]
+    fn three_column_distance_matrix() {
+        let input = "3 4 1\n4 3 1\n2 5 1\n\n1 3 1\n3 9 1\n3 3 1\n";
+        assert_eq!(
+            distance_matrix(&mut input.as_bytes()),
+            Ok(vec![vec![0, 11, 10], vec![11, 0, 21], vec![10, 21, 0]])
+        );
+    }
+
+    #[test]
+    fn malformed_rows_are_reported() {
+        assert_eq!(
+            parse_columns(&mut "1 2\n3 x\n".as_bytes()),
+            Err(ParseError::InvalidNumber { line: 2, value: "x".to_string() })
+        );
+        assert_eq!(
+            parse_columns(&mut "1 2\n3 4 5\n".as_bytes()),
+            Err(ParseError::ColumnCount { line: 2, expected: 2, found: 3 })
+        );
+        assert_eq!(
+            what_is_the_total_distance_between_lists(&mut "".as_bytes()),
+            Err(ParseError::TooFewColumns { expected: 2, found: 0 })
+        );
+        assert_eq!(
+            what_is_lists_similarity_score(&mut "1\n2\n".as_bytes()),
+            Err(ParseError::TooFewColumns { expected: 2, found: 1 })
+        );
+    }
+
+    #[test]
+    fn million_rows_with_large_totals() {
+        struct Rows {
+            next: u64,
+            buffer: Vec<u8>,
+            position: usize,
+        }
+
+        impl std::io::Read for Rows {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                if self.position == self.buffer.len() {
+                    if self.next == 1_000_000 {
+                        return Ok(0);
+                    }
+                    self.buffer = format!("{}   {}\n", 4_000_000_000 + self.next, self.next).into_bytes();
+                    self.position = 0;
+                    self.next += 1;
+                }
+                let n = buf.len().min(self.buffer.len() - self.position);
+                buf[..n].copy_from_slice(&self.buffer[self.position..self.position + n]);
+                self.position += n;
+                Ok(n)
+            }
+        }
+
+        let rows = || BufReader::new(Rows { next: 0, buffer: Vec::new(), position: 0 });
+        assert_eq!(what_is_the_total_distance_between_lists(&mut rows()), Ok(4_000_000_000_000_000));
+        assert_eq!(what_is_lists_similarity_score(&mut rows()), Ok(0));
     }
 }
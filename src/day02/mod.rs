@@ -3,43 +3,157 @@ use std::io::BufRead;
 use crate::read_input;
 
 pub fn count_safe_reports(input: &mut dyn BufRead) -> usize {
-  let reports = parse_input(read_input(input));
-  reports.iter().filter(|r| is_report_safe(r)).count()
+  count_safe_reports_with_dampener(input, &Dampener::default())
 }
 
 pub fn count_safe_reports_with_toleration(input: &mut dyn BufRead) -> usize {
+  count_safe_reports_with_dampener(input, &Dampener::default().with_tolerance(1))
+}
+
+pub fn count_safe_reports_with_dampener(input: &mut dyn BufRead, dampener: &Dampener) -> usize {
   let reports = parse_input(read_input(input));
-  reports.iter().filter(|r| is_report_safe_with_toleration(r)).count()
+  reports.iter().filter(|r| dampener.is_safe(r)).count()
+}
+
+pub fn diagnose_reports(input: &mut dyn BufRead, dampener: &Dampener) -> Vec<Diagnosis> {
+  parse_input(read_input(input))
+    .into_iter()
+    .map(|report| Diagnosis {
+      problems: dampener.problems(&report),
+      removed: dampener.levels_to_remove(&report),
+      report,
+    })
+    .collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UnsafeReason {
+  Flat,
+  DirectionChange,
+  StepTooSmall(u32),
+  StepTooLarge(u32),
 }
 
-fn is_report_safe_with_toleration(report: &Vec<u32>) -> bool {
-  is_report_safe(report)
-    || (0..report.len()).any(|level| is_report_safe_without_level(report, level))
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LevelProblem {
+  pub position: usize,
+  pub reason: UnsafeReason,
 }
 
-fn is_report_safe_without_level(report: &Vec<u32>, level: usize) -> bool {
-  fn without_level(report: &Vec<u32>, level: usize) -> Vec<u32> {
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnosis {
+  pub report: Vec<u32>,
+  pub problems: Vec<LevelProblem>,
+  pub removed: Option<Vec<usize>>,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Dampener {
+  min_step: u32,
+  max_step: u32,
+  tolerance: usize,
+}
+
+impl Default for Dampener {
+  fn default() -> Self {
+    Dampener {
+      min_step: 1,
+      max_step: 3,
+      tolerance: 0,
+    }
+  }
+}
+
+impl Dampener {
+  pub fn with_steps(self, min_step: u32, max_step: u32) -> Self {
+    Dampener { min_step, max_step, ..self }
+  }
+
+  pub fn with_tolerance(self, tolerance: usize) -> Self {
+    Dampener { tolerance, ..self }
+  }
+
+  pub fn is_safe(&self, report: &[u32]) -> bool {
+    self.levels_to_remove(report).is_some()
+  }
+
+  pub fn problems(&self, report: &[u32]) -> Vec<LevelProblem> {
+    let increasing = report.windows(2).filter(|w| w[1] > w[0]).count();
+    let decreasing = report.windows(2).filter(|w| w[1] < w[0]).count();
+    let ascending = increasing >= decreasing;
+
     report
-      .iter()
+      .windows(2)
       .enumerate()
-      .filter(|&(i, _)| i != level)
-      .map(|(_, v)| *v)
-      .collect::<Vec<u32>>()
+      .flat_map(|(i, w)| {
+        self.check_step(w[0], w[1], ascending).map(|reason| LevelProblem { position: i + 1, reason })
+      })
+      .collect()
   }
 
-  is_report_safe(&without_level(report, level))
-}
+  pub fn levels_to_remove(&self, report: &[u32]) -> Option<Vec<usize>> {
+    [true, false]
+      .iter()
+      .flat_map(|&ascending| self.levels_to_remove_in_direction(report, ascending))
+      .min_by_key(|removed| removed.len())
+  }
 
-fn is_report_safe(report: &Vec<u32>) -> bool {
-  are_levels_monothonic(report, |d| d >= 1 && d <= 3)
-    || are_levels_monothonic(report, |d| d <= -1 && d >= -3)
-}
+  fn levels_to_remove_in_direction(&self, report: &[u32], ascending: bool) -> Option<Vec<usize>> {
+    let n = report.len();
+    let k = self.tolerance;
+    if n == 0 {
+      return Some(vec![]);
+    }
+
+    // removed[j]: fewest levels dropped so far if level j is kept, with the kept level before it in previous[j]
+    let mut removed: Vec<Option<usize>> = vec![None; n];
+    let mut previous: Vec<Option<usize>> = vec![None; n];
+
+    for j in 0..n {
+      if j <= k {
+        removed[j] = Some(j);
+      }
+      for i in j.saturating_sub(k + 1)..j {
+        if let Some(r) = removed[i]
+          && self.check_step(report[i], report[j], ascending).is_none()
+          && r + (j - i - 1) <= k
+          && removed[j].is_none_or(|best| r + (j - i - 1) < best)
+        {
+          removed[j] = Some(r + (j - i - 1));
+          previous[j] = Some(i);
+        }
+      }
+    }
+
+    let last = ((n - 1).saturating_sub(k)..n)
+      .filter(|&j| removed[j].is_some_and(|r| r + (n - 1 - j) <= k))
+      .min_by_key(|&j| removed[j].unwrap() + (n - 1 - j))?;
+
+    let mut kept = vec![false; n];
+    let mut level = Some(last);
+    while let Some(i) = level {
+      kept[i] = true;
+      level = previous[i];
+    }
 
-fn are_levels_monothonic(report: &Vec<u32>, f: fn(i32) -> bool) -> bool {
-  report
-    .windows(2)
-    .map(|w| w[0] as i32 - w[1] as i32)
-    .all(|d| f(d))
+    Some((0..n).filter(|&i| !kept[i]).collect())
+  }
+
+  fn check_step(&self, from: u32, to: u32, ascending: bool) -> Option<UnsafeReason> {
+    let step = from.abs_diff(to);
+
+    if step == 0 && self.min_step > 0 {
+      Some(UnsafeReason::Flat)
+    } else if step != 0 && (to > from) != ascending {
+      Some(UnsafeReason::DirectionChange)
+    } else if step < self.min_step {
+      Some(UnsafeReason::StepTooSmall(step))
+    } else if step > self.max_step {
+      Some(UnsafeReason::StepTooLarge(step))
+    } else {
+      None
+    }
+  }
 }
 
 fn parse_input(input: Vec<String>) -> Vec<Vec<u32>> {
@@ -58,7 +172,15 @@ fn parse_input(input: Vec<String>) -> Vec<Vec<u32>> {
 
 #[cfg(test)]
 mod tests {
-  use crate::{day02::{count_safe_reports, count_safe_reports_with_toleration}, read};
+  use itertools::Itertools;
+
+  use crate::{
+    day02::{
+      count_safe_reports, count_safe_reports_with_dampener, count_safe_reports_with_toleration, diagnose_reports,
+      Dampener, LevelProblem, UnsafeReason,
+    },
+    read, read_input,
+  };
 
   #[test]
   fn sample_part1_input() {
@@ -79,4 +201,70 @@ mod tests {
   fn my_part2_input() {
     assert_eq!(count_safe_reports_with_toleration(&mut read("./src/day02/my.input")), 439);
   }
+
+  #[test]
+  fn sample_diagnostics() {
+    let diagnoses = diagnose_reports(&mut read("./src/day02/sample.input"), &Dampener::default().with_tolerance(1));
+
+    assert!(diagnoses[0].problems.is_empty());
+    assert_eq!(diagnoses[0].removed, Some(vec![]));
+    assert_eq!(
+      diagnoses[1].problems,
+      vec![LevelProblem { position: 2, reason: UnsafeReason::StepTooLarge(5) }]
+    );
+    assert_eq!(diagnoses[1].removed, None);
+    assert_eq!(
+      diagnoses[3].problems,
+      vec![LevelProblem { position: 2, reason: UnsafeReason::DirectionChange }]
+    );
+    assert_eq!(diagnoses[3].removed, Some(vec![2]));
+    assert_eq!(
+      diagnoses[4].problems,
+      vec![LevelProblem { position: 3, reason: UnsafeReason::Flat }]
+    );
+    assert_eq!(diagnoses[4].removed, Some(vec![3]));
+  }
+
+  #[test]
+  fn sample_with_wider_steps() {
+    let dampener = Dampener::default().with_steps(1, 5);
+    assert_eq!(count_safe_reports_with_dampener(&mut read("./src/day02/sample.input"), &dampener), 4);
+    assert_eq!(
+      count_safe_reports_with_dampener(&mut read("./src/day02/sample.input"), &dampener.with_steps(2, 5)),
+      0
+    );
+  }
+
+  #[test]
+  fn my_input_tolerance_matches_brute_force() {
+    let reports = read_input(&mut read("./src/day02/my.input"))
+      .iter()
+      .map(|line| line.split(' ').map(|c| c.parse::<u32>().unwrap()).collect_vec())
+      .collect_vec();
+
+    for k in 0..=3 {
+      let dampener = Dampener::default().with_tolerance(k);
+      let strict = Dampener::default();
+      let brute_force = reports
+        .iter()
+        .filter(|report| {
+          (report.len().saturating_sub(k)..=report.len())
+            .any(|size| report.iter().copied().combinations(size).any(|kept| strict.is_safe(&kept)))
+        })
+        .count();
+
+      assert_eq!(reports.iter().filter(|report| dampener.is_safe(report)).count(), brute_force);
+      for report in reports.iter() {
+        if let Some(removed) = dampener.levels_to_remove(report) {
+          let kept = report
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !removed.contains(i))
+            .map(|(_, level)| *level)
+            .collect_vec();
+          assert!(removed.len() <= k && strict.is_safe(&kept));
+        }
+      }
+    }
+  }
 }
@@ -0,0 +1,45 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use day01::day04::{SearchOptions, WordSearch};
+
+fn generate_board(size: usize) -> String {
+  let letters = ['X', 'M', 'A', 'S'];
+  let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+
+  (0..size)
+    .map(|_| {
+      (0..size)
+        .map(|_| {
+          seed ^= seed << 13;
+          seed ^= seed >> 7;
+          seed ^= seed << 17;
+          letters[(seed % letters.len() as u64) as usize]
+        })
+        .collect::<String>()
+    })
+    .collect::<Vec<String>>()
+    .join("\n")
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+  let mut group = c.benchmark_group("word_search");
+  group.sample_size(10);
+
+  let words = ["XMAS", "SAMX", "MAS", "XMASXMAS"];
+  let options = SearchOptions::default();
+
+  for size in [140, 500, 1000] {
+    let search = WordSearch::new(&mut generate_board(size).as_bytes());
+
+    group.bench_with_input(BenchmarkId::new("per_cell", size), &search, |b, search| {
+      b.iter(|| search.find_words(&words, &options))
+    });
+    group.bench_with_input(BenchmarkId::new("aho_corasick", size), &search, |b, search| {
+      b.iter(|| search.find_words_along_lines(&words, &options))
+    });
+  }
+
+  group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
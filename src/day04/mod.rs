@@ -1,133 +1,295 @@
-use std::io::BufRead;
+use std::{collections::HashSet, io::BufRead};
 
-use itertools::Itertools;
+use aho_corasick::AhoCorasick;
+use itertools::{iproduct, Itertools};
 
 use crate::{read_input, CartesianGrid, Coords, GridCoords, ICoords};
 
 pub fn count_xmas_word(input: &mut dyn BufRead) -> usize {
-  let word = "XMAS".chars().collect_vec();
-
-  let board = CartesianGrid::from(read_input(input));
-  board
-    .coords()
-    .iter()
-    .map(|c| board.count_word(c, &word))
-    .sum()
+  WordSearch::new(input)
+    .find_words(&["XMAS"], &SearchOptions::default())
+    .len()
 }
 
 pub fn count_x_mas(input: &mut dyn BufRead) -> usize {
-  let word = "MAS".chars().collect_vec();
-
-  let board = CartesianGrid::from(read_input(input));
-  board
-    .coords()
-    .iter()
-    .map(|c| board.find_word_on_diagonals(c, &word))
-    .flat_map(|words| {
-      words
-        .iter()
-        .map(|w| *w.get(1).unwrap())
-        .collect::<Vec<Coords>>()
-    })
-    .into_group_map_by(|&n| n)
-    .into_iter()
-    .filter(|(_, v)| v.len() == 2)
-    .count()
+  let x_mas = Stencil::new(&["M.S", ".A.", "M.S"]);
+
+  WordSearch::new(input).find_stencils(&x_mas.rotations()).len()
 }
 
-impl CartesianGrid<char> {
-  fn count_word(&self, start: &Coords, word: &Vec<char>) -> usize {
-    if *self.get(start) != *word.get(0).unwrap() {
-      return 0;
-    } else {
-      self
-        .get_versors()
-        .iter()
-        .filter(|v| self.word_exists_in_direction(start, v, word))
-        .count()
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
+  North,
+  NorthEast,
+  East,
+  SouthEast,
+  South,
+  SouthWest,
+  West,
+  NorthWest,
+}
+
+impl Direction {
+  pub const ORTHOGONAL: [Direction; 4] = [Direction::North, Direction::East, Direction::South, Direction::West];
+  pub const DIAGONAL: [Direction; 4] =
+    [Direction::NorthEast, Direction::SouthEast, Direction::SouthWest, Direction::NorthWest];
+  pub const ALL: [Direction; 8] = [
+    Direction::North,
+    Direction::NorthEast,
+    Direction::East,
+    Direction::SouthEast,
+    Direction::South,
+    Direction::SouthWest,
+    Direction::West,
+    Direction::NorthWest,
+  ];
+
+  fn vector(&self) -> ICoords {
+    match self {
+      Direction::North => ICoords(0, -1),
+      Direction::NorthEast => ICoords(1, -1),
+      Direction::East => ICoords(1, 0),
+      Direction::SouthEast => ICoords(1, 1),
+      Direction::South => ICoords(0, 1),
+      Direction::SouthWest => ICoords(-1, 1),
+      Direction::West => ICoords(-1, 0),
+      Direction::NorthWest => ICoords(-1, -1),
     }
   }
+}
 
-  fn find_word_on_diagonals(
-    &self,
-    start: &Coords,
-    word: &Vec<char>,
-  ) -> Vec<Vec<Coords>> {
-    if *self.get(start) != *word.get(0).unwrap() {
-      return vec![];
-    } else {
-      self
-        .get_diagonal_versors()
-        .iter()
-        .filter(|v| self.word_exists_in_direction(start, v, word))
-        .map(|v| self.make_word_coords(start, v, word.len()))
-        .collect()
+#[derive(Clone, Debug)]
+pub struct SearchOptions {
+  directions: Vec<Direction>,
+  wrap_around: bool,
+}
+
+impl Default for SearchOptions {
+  fn default() -> Self {
+    SearchOptions {
+      directions: Direction::ALL.to_vec(),
+      wrap_around: false,
     }
   }
+}
+
+impl SearchOptions {
+  pub fn with_directions(self, directions: &[Direction]) -> Self {
+    SearchOptions { directions: directions.to_vec(), ..self }
+  }
+
+  pub fn with_wrap_around(self, wrap_around: bool) -> Self {
+    SearchOptions { wrap_around, ..self }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct WordMatch {
+  pub word: String,
+  pub start: Coords,
+  pub direction: Direction,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Stencil {
+  width: usize,
+  height: usize,
+  cells: Vec<(Coords, char)>,
+}
 
-  fn word_exists_in_direction(
-    &self,
-    start: &Coords,
-    direction: &ICoords,
-    word: &Vec<char>,
-  ) -> bool {
-    if let Some(coords) = self.get_word_coords(start, direction, word.len()) {
-      coords
+impl Stencil {
+  pub const WILDCARD: char = '.';
+
+  pub fn new(rows: &[&str]) -> Self {
+    let cells = rows
+      .iter()
+      .enumerate()
+      .flat_map(|(y, row)| row.chars().enumerate().map(move |(x, c)| (Coords::new(x, y), c)))
+      .filter(|(_, c)| *c != Stencil::WILDCARD)
+      .collect_vec();
+
+    Stencil {
+      width: rows.iter().map(|row| row.chars().count()).max().unwrap_or(0),
+      height: rows.len(),
+      cells,
+    }
+  }
+
+  pub fn rotate(&self) -> Stencil {
+    Stencil {
+      width: self.height,
+      height: self.width,
+      cells: self
+        .cells
         .iter()
-        .enumerate()
-        .all(|(i, c)| self.get(c) == word.get(i).unwrap())
-    } else {
-      false
+        .map(|(c, v)| (Coords::new(self.height - 1 - c.1, c.0), *v))
+        .sorted_by_key(|(c, _)| (c.1, c.0))
+        .collect(),
     }
   }
 
-  fn get_word_coords(
-    &self,
-    start: &Coords,
-    direction: &ICoords,
-    distance: usize,
-  ) -> Option<Vec<Coords>> {
-    if (start + direction * (distance as i32 - 1)).in_grid(self) {
-      Some(
-        self.make_word_coords(start, direction, distance)
-      )
-    } else {
-      None
+  pub fn rotations(&self) -> Vec<Stencil> {
+    let mut rotations = vec![self.clone()];
+    for _ in 1..4 {
+      let next = rotations.last().unwrap().rotate();
+      if !rotations.contains(&next) {
+        rotations.push(next);
+      }
     }
+    rotations
   }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct StencilMatch {
+  pub stencil: usize,
+  pub position: Coords,
+}
 
-  fn make_word_coords(
-    &self,
-    start: &Coords,
-    direction: &ICoords,
-    distance: usize,
-  ) -> Vec<Coords> {
-    (0..distance)
-      .flat_map(|d| (start + direction * d as i32).to_coords())
-      .collect::<Vec<Coords>>()
+pub struct WordSearch {
+  board: CartesianGrid<char>,
+}
+
+impl WordSearch {
+  pub fn new(input: &mut dyn BufRead) -> Self {
+    WordSearch {
+      board: CartesianGrid::from(read_input(input)),
+    }
+  }
+
+  pub fn find_words(&self, words: &[&str], options: &SearchOptions) -> Vec<WordMatch> {
+    let words = words.iter().map(|w| w.chars().collect_vec()).collect_vec();
+
+    iproduct!(self.board.coords(), options.directions.iter(), words.iter())
+      .filter(|(start, direction, word)| self.word_exists_in_direction(start, direction, word, options.wrap_around))
+      .map(|(start, direction, word)| WordMatch {
+        word: word.iter().collect(),
+        start,
+        direction: *direction,
+      })
+      .collect()
   }
 
-  fn get_versors(&self) -> Vec<ICoords> {
-    return vec![
-      ICoords(0, 1),
-      ICoords(1, 1),
-      ICoords(1, 0),
-      ICoords(1, -1),
-      ICoords(0, -1),
-      ICoords(-1, -1),
-      ICoords(-1, 0),
-      ICoords(-1, 1),
-    ];
+  pub fn find_words_along_lines(&self, words: &[&str], options: &SearchOptions) -> Vec<WordMatch> {
+    let automaton = AhoCorasick::new(words).unwrap();
+    let longest = words.iter().map(|w| w.chars().count()).max().unwrap_or(0);
+    let mut matches: Vec<(Coords, usize, usize)> = Vec::new();
+
+    for (d, direction) in options.directions.iter().enumerate() {
+      for line in self.lines(direction, options.wrap_around) {
+        let length = if options.wrap_around { line.len() + longest.max(1) - 1 } else { line.len() };
+        let text: String = line.iter().cycle().take(length).map(|c| *self.board.get(c)).collect();
+        let char_index = text
+          .chars()
+          .enumerate()
+          .flat_map(|(i, c)| std::iter::repeat_n(i, c.len_utf8()))
+          .collect_vec();
+
+        matches.extend(
+          automaton
+            .find_overlapping_iter(text.as_str())
+            .map(|m| (char_index[m.start()], m.pattern().as_usize()))
+            .filter(|(start, _)| *start < line.len())
+            .map(|(start, word)| (line[start], d, word)),
+        );
+      }
+    }
+
+    matches
+      .into_iter()
+      .sorted_by_key(|(start, d, word)| (start.1, start.0, *d, *word))
+      .map(|(start, d, word)| WordMatch {
+        word: words[word].to_string(),
+        start,
+        direction: options.directions[d],
+      })
+      .collect()
   }
 
-  fn get_diagonal_versors(&self) -> Vec<ICoords> {
-    return vec![ICoords(1, 1), ICoords(1, -1), ICoords(-1, -1), ICoords(-1, 1)];
+  pub fn find_stencils(&self, stencils: &[Stencil]) -> Vec<StencilMatch> {
+    self
+      .board
+      .coords()
+      .into_iter()
+      .flat_map(|position| {
+        stencils
+          .iter()
+          .enumerate()
+          .filter(move |(_, stencil)| self.stencil_matches_at(&position, stencil))
+          .map(move |(stencil, _)| StencilMatch { stencil, position })
+      })
+      .collect()
   }
+
+  fn lines(&self, direction: &Direction, wrap_around: bool) -> Vec<Vec<Coords>> {
+    let (width, height) = (self.board.grid[0].len(), self.board.grid.len());
+    let step = direction.vector();
+    let mut visited: HashSet<Coords> = HashSet::new();
+    let mut lines: Vec<Vec<Coords>> = Vec::new();
+
+    for start in self.board.coords() {
+      if wrap_around {
+        if visited.contains(&start) {
+          continue;
+        }
+
+        let mut line = vec![start];
+        let mut next = (start + step).rem_euclid(width, height);
+        while next != start {
+          line.push(next);
+          next = (next + step).rem_euclid(width, height);
+        }
+        visited.extend(line.iter().copied());
+        lines.push(line);
+      } else if !(start + opposite(&step)).in_grid(&self.board) {
+        lines.push(
+          (0..)
+            .map(|i: usize| start + step * i)
+            .take_while(|c| c.in_grid(&self.board))
+            .flat_map(|c| c.to_coords())
+            .collect(),
+        );
+      }
+    }
+
+    lines
+  }
+
+  fn word_exists_in_direction(&self, start: &Coords, direction: &Direction, word: &[char], wrap_around: bool) -> bool {
+    (0..word.len())
+      .map(|d| *start + direction.vector() * d)
+      .zip(word.iter())
+      .all(|(c, letter)| self.letter_at(&c, wrap_around) == Some(*letter))
+  }
+
+  fn stencil_matches_at(&self, position: &Coords, stencil: &Stencil) -> bool {
+    stencil
+      .cells
+      .iter()
+      .all(|(c, letter)| self.letter_at(&(*position + ICoords(c.0 as isize, c.1 as isize)), false) == Some(*letter))
+  }
+
+  fn letter_at(&self, coords: &ICoords, wrap_around: bool) -> Option<char> {
+    if wrap_around {
+      Some(*self.board.get(&coords.rem_euclid(self.board.grid[0].len(), self.board.grid.len())))
+    } else if coords.in_grid(&self.board) {
+      coords.to_coords().map(|c| *self.board.get(&c))
+    } else {
+      None
+    }
+  }
+}
+
+fn opposite(vector: &ICoords) -> ICoords {
+  ICoords(-vector.0, -vector.1)
 }
 
 #[cfg(test)]
 mod tests {
-  use crate::{day04::{count_x_mas, count_xmas_word}, read};
+  use crate::{
+    day04::{count_x_mas, count_xmas_word, Direction, SearchOptions, Stencil, WordMatch, WordSearch},
+    read, Coords,
+  };
 
   #[test]
   fn sample_part1_input() {
@@ -148,4 +310,87 @@ mod tests {
   fn my_part2_input() {
     assert_eq!(count_x_mas(&mut read("./src/day04/my.input")), 1807);
   }
+
+  #[test]
+  fn sample_words_in_orthogonal_directions() {
+    let search = WordSearch::new(&mut read("./src/day04/sample.input"));
+    let options = SearchOptions::default().with_directions(&Direction::ORTHOGONAL);
+    let matches = search.find_words(&["XMAS", "SAMX"], &options);
+
+    assert_eq!(matches.len(), 2 * (3 + 2 + 1 + 2));
+    assert!(matches.contains(&WordMatch {
+      word: "XMAS".to_string(),
+      start: Coords::new(5, 0),
+      direction: Direction::East
+    }));
+  }
+
+  #[test]
+  fn words_wrap_around_board_edges() {
+    let board = "ASXM\n....";
+    let mut search = WordSearch::new(&mut board.as_bytes());
+    let east = SearchOptions::default().with_directions(&[Direction::East]);
+
+    assert!(search.find_words(&["XMAS"], &east).is_empty());
+    assert_eq!(
+      search.find_words(&["XMAS"], &east.with_wrap_around(true)),
+      vec![WordMatch {
+        word: "XMAS".to_string(),
+        start: Coords::new(2, 0),
+        direction: Direction::East
+      }]
+    );
+
+    search = WordSearch::new(&mut "X\nM\nA\nS".as_bytes());
+    let vertical = SearchOptions::default().with_directions(&[Direction::North]).with_wrap_around(true);
+    assert_eq!(search.find_words(&["XSAM"], &vertical).len(), 1);
+  }
+
+  #[test]
+  fn stencil_rotations_are_distinct() {
+    assert_eq!(Stencil::new(&["M.S", ".A.", "M.S"]).rotations().len(), 4);
+    assert_eq!(Stencil::new(&["M.M", ".A.", "M.M"]).rotations().len(), 1);
+    assert_eq!(Stencil::new(&["XMAS"]).rotate(), Stencil::new(&["X", "M", "A", "S"]));
+  }
+
+  #[test]
+  fn sample_straight_stencil_matches_orthogonal_words() {
+    let search = WordSearch::new(&mut read("./src/day04/sample.input"));
+    let options = SearchOptions::default().with_directions(&Direction::ORTHOGONAL);
+
+    assert_eq!(
+      search.find_stencils(&Stencil::new(&["XMAS"]).rotations()).len(),
+      search.find_words(&["XMAS"], &options).len()
+    );
+    assert!(search.find_stencils(&Stencil::new(&[".M.", "MAS", ".S."]).rotations()).is_empty());
+  }
+
+  #[test]
+  fn my_input_line_search_matches_per_cell_search() {
+    let search = WordSearch::new(&mut read("./src/day04/my.input"));
+    let words = ["XMAS", "MAS", "SAMX", "XX"];
+
+    for options in [
+      SearchOptions::default(),
+      SearchOptions::default().with_directions(&Direction::DIAGONAL),
+      SearchOptions::default().with_wrap_around(true),
+    ] {
+      let matches = search.find_words_along_lines(&words, &options);
+      assert!(!matches.is_empty());
+      assert_eq!(matches, search.find_words(&words, &options));
+    }
+  }
+
+  #[test]
+  fn line_search_handles_words_longer_than_wrapped_lines() {
+    let search = WordSearch::new(&mut "XM\nAS".as_bytes());
+    let options = SearchOptions::default().with_wrap_around(true);
+    let words = ["XMXMX", "XSXS"];
+
+    assert_eq!(
+      search.find_words_along_lines(&words, &options),
+      search.find_words(&words, &options)
+    );
+    assert_eq!(search.find_words(&words, &options).len(), 2 + 4);
+  }
 }
@@ -1,5 +1,9 @@
 use itertools::Itertools;
-use std::{collections::HashMap, io::BufRead};
+use std::{
+  collections::{HashMap, HashSet},
+  fmt,
+  io::BufRead,
+};
 
 use crate::read_input;
 
@@ -8,163 +12,502 @@ type Update = Vec<u32>;
 
 pub fn sum_middle_page_numbers_of_correct_updates(input: &mut dyn BufRead) -> u32 {
   let (rules, updates) = parse_input(read_input(input));
+  let ordering = PageOrdering::parse(&rules);
   updates
     .iter()
-    .filter(|u| is_correctly_ordered(u, &rules))
+    .filter(|u| ordering.is_correctly_ordered(u))
     .map(|u| get_middle_page(u))
     .sum()
 }
 
-pub fn sum_middle_page_numbers_of_corrected_updates(input: &mut dyn BufRead) -> u32 {
+pub fn sum_middle_page_numbers_of_corrected_updates(input: &mut dyn BufRead) -> Result<u32, OrderingError> {
   let (rules, updates) = parse_input(read_input(input));
+  let ordering = PageOrdering::parse(&rules);
   updates
     .iter()
-    .filter(|u| !is_correctly_ordered(u, &rules))
-    .map(|u| correct_the_update(u, &rules))
-    .map(|u| *get_middle_page(&u))
+    .filter(|u| !ordering.is_correctly_ordered(u))
+    .map(|u| ordering.sort(u).map(|u| *get_middle_page(&u)))
     .sum()
 }
 
-fn parse_input(lines: Vec<String>) -> (Vec<Rule>, Vec<Update>) {
-  fn parse_rule(line: &String) -> Rule {
-    line
-      .split_once('|')
-      .map(|x| (x.0.parse::<u32>().unwrap(), x.1.parse::<u32>().unwrap()))
-      .unwrap()
-  }
-
-  fn parse_update(line: &String) -> Vec<u32> {
-    line
-      .split(',')
-      .map(|page| page.parse::<u32>().unwrap())
-      .collect()
-  }
-
-  let sections = lines
-    .split(|line| line == "")
-    .map(|ll| ll.iter().map(String::from).collect())
-    .collect::<Vec<Vec<String>>>();
-  let rules = sections
-    .first()
-    .unwrap()
-    .iter()
-    .map(|line| parse_rule(line))
-    .collect::<Vec<Rule>>();
-  let updates = sections
-    .last()
-    .unwrap()
+pub fn explain_incorrect_updates(input: &mut dyn BufRead) -> String {
+  let (rules, updates) = parse_input(read_input(input));
+  let ordering = PageOrdering::parse(&rules);
+  updates
     .iter()
-    .map(|line| parse_update(line))
-    .collect::<Vec<Update>>();
-
-  (rules, updates)
+    .filter(|u| !ordering.is_correctly_ordered(u))
+    .map(|u| match ordering.explain(u) {
+      Ok(explanation) => format!("{}: {}", u.iter().join(","), explanation),
+      Err(error) => format!("{}: cannot be corrected: {:?}\n", u.iter().join(","), error),
+    })
+    .collect()
 }
 
-fn is_correctly_ordered(update: &Update, rules: &Vec<Rule>) -> bool {
-  update
-    .iter()
-    .enumerate()
-    .all(|page| is_correctly_placed(page.0, update, rules))
+#[derive(Debug, PartialEq)]
+pub enum OrderingError {
+  Cycle(Vec<u32>),
+  Ambiguous(Vec<u32>),
 }
 
-fn is_correctly_placed(page_number: usize, update: &Update, rules: &Vec<Rule>) -> bool {
-  (page_number + 1..update.len()).all(|after| {
-    page_follows_rules(
-      update.get(page_number).unwrap(),
-      update.get(after).unwrap(),
-      rules,
-    )
-  })
+#[derive(Clone, Debug, PartialEq)]
+pub struct RuleViolation {
+  pub rule: String,
+  pub before: u32,
+  pub after: u32,
+  pub before_position: usize,
+  pub after_position: usize,
 }
 
-fn page_follows_rules(page: &u32, against: &u32, rules: &Vec<Rule>) -> bool {
-  rules
-    .iter()
-    .filter(|r| rule_applies(page, against, r))
-    .all(|r| page_follows_rule(page, against, r))
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PageMove {
+  pub page: u32,
+  pub from: usize,
+  pub to: usize,
 }
 
-fn rule_applies(page: &u32, against: &u32, rule: &Rule) -> bool {
-  (*page == rule.0 || *page == rule.1) && (*against == rule.0 || *against == rule.1)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Explanation {
+  pub violations: Vec<RuleViolation>,
+  pub corrected: Update,
+  pub swaps: Vec<(usize, usize)>,
+  pub moves: Vec<PageMove>,
+  pub ambiguous: Option<Vec<u32>>,
 }
 
-fn page_follows_rule(page: &u32, against: &u32, rule: &Rule) -> bool {
-  *page == rule.0 && *against == rule.1
+impl fmt::Display for Explanation {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(f, "{} violated rule(s), corrected to {}", self.violations.len(), self.corrected.iter().join(","))?;
+    for v in self.violations.iter() {
+      writeln!(
+        f,
+        "  rule {} broken: {} at position {} comes after {} at position {}",
+        v.rule, v.before, v.before_position, v.after, v.after_position
+      )?;
+    }
+    writeln!(
+      f,
+      "  {} swap(s): {}",
+      self.swaps.len(),
+      self.swaps.iter().map(|(i, j)| format!("{}<->{}", i, j)).join(" ")
+    )?;
+    writeln!(
+      f,
+      "  {} move(s): {}",
+      self.moves.len(),
+      self.moves.iter().map(|m| format!("{} {}->{}", m.page, m.from, m.to)).join(" ")
+    )?;
+    if let Some(pages) = self.ambiguous.as_ref() {
+      writeln!(
+        f,
+        "  note: rules do not order pages {}, kept in their original order",
+        pages.iter().join(",")
+      )?;
+    }
+    Ok(())
+  }
 }
 
-fn get_middle_page(update: &Update) -> &u32 {
-  update.get(update.len() / 2).unwrap()
+pub struct PageOrdering {
+  successors: HashMap<u32, HashSet<u32>>,
+  rule_lines: HashMap<Rule, String>,
 }
 
-fn correct_the_update(update: &Update, rules: &Vec<Rule>) -> Update {
-  let graph = make_rules_graph(rules);
-  let mut corrected = update.clone();
+impl PageOrdering {
+  pub fn new(rules: &[Rule]) -> Self {
+    let lines = rules.iter().map(|(before, after)| format!("{}|{}", before, after)).collect_vec();
+    Self::with_lines(rules, &lines)
+  }
+
+  pub fn parse(lines: &[String]) -> Self {
+    Self::with_lines(&lines.iter().map(|line| parse_rule(line)).collect_vec(), lines)
+  }
+
+  fn with_lines(rules: &[Rule], lines: &[String]) -> Self {
+    let mut successors: HashMap<u32, HashSet<u32>> = HashMap::new();
+    for (before, after) in rules {
+      successors.entry(*before).or_default().insert(*after);
+      successors.entry(*after).or_default();
+    }
 
-  for i in (0..corrected.len()).rev() {
-    let to_check = corrected.iter().take(i + 1).collect::<Vec<&u32>>();
-    let (pos, _) = to_check
+    let rule_lines = rules.iter().copied().zip(lines.iter().map(|line| line.trim().to_string())).collect();
+
+    PageOrdering { successors, rule_lines }
+  }
+
+  pub fn validate(&self) -> Result<(), OrderingError> {
+    let pages: HashSet<u32> = self.successors.keys().copied().collect();
+    match self.find_cycle(&pages) {
+      Some(cycle) => Err(OrderingError::Cycle(cycle)),
+      None => Ok(()),
+    }
+  }
+
+  pub fn is_correctly_ordered(&self, update: &[u32]) -> bool {
+    update
+      .iter()
+      .enumerate()
+      .all(|(i, page)| update[i + 1..].iter().all(|after| !self.must_precede(after, page)))
+  }
+
+  pub fn sort(&self, update: &[u32]) -> Result<Update, OrderingError> {
+    self.topological_sort(update, true)
+  }
+
+  pub fn correct(&self, update: &[u32]) -> Result<Update, OrderingError> {
+    self.topological_sort(update, false)
+  }
+
+  fn topological_sort(&self, update: &[u32], strict: bool) -> Result<Update, OrderingError> {
+    let pages: HashSet<u32> = update.iter().copied().collect();
+    let mut in_degree: HashMap<u32, usize> = pages.iter().map(|&p| (p, 0)).collect();
+    for page in pages.iter() {
+      for next in self.successors_within(page, &pages) {
+        *in_degree.get_mut(&next).unwrap() += 1;
+      }
+    }
+
+    let mut ready = in_degree.iter().filter(|(_, d)| **d == 0).map(|(p, _)| *p).collect_vec();
+    let mut sorted: Update = Vec::new();
+
+    while !ready.is_empty() {
+      if strict && ready.len() > 1 {
+        return Err(OrderingError::Ambiguous(ready.into_iter().sorted().collect()));
+      }
+
+      let index = ready.iter().position_min_by_key(|p| update.iter().position(|u| u == *p)).unwrap();
+      let page = ready.swap_remove(index);
+      sorted.push(page);
+      for next in self.successors_within(&page, &pages) {
+        let degree = in_degree.get_mut(&next).unwrap();
+        *degree -= 1;
+        if *degree == 0 {
+          ready.push(next);
+        }
+      }
+    }
+
+    if sorted.len() < pages.len() {
+      let remaining: HashSet<u32> = pages.difference(&sorted.iter().copied().collect()).copied().collect();
+      return Err(OrderingError::Cycle(self.find_cycle(&remaining).unwrap()));
+    }
+
+    Ok(sorted)
+  }
+
+  pub fn violations(&self, update: &[u32]) -> Vec<RuleViolation> {
+    update
       .iter()
-      .find_position(|v| has_no_deps(v, &values_except(v, &to_check), &graph))
-      .unwrap();
-    corrected.swap(i, pos);
+      .enumerate()
+      .tuple_combinations()
+      .filter(|((_, page), (_, after))| self.must_precede(after, page))
+      .map(|((i, page), (j, after))| RuleViolation {
+        rule: self.rule_lines[&(*after, *page)].clone(),
+        before: *after,
+        after: *page,
+        before_position: j,
+        after_position: i,
+      })
+      .collect()
+  }
+
+  pub fn explain(&self, update: &[u32]) -> Result<Explanation, OrderingError> {
+    let corrected = self.correct(update)?;
+    let ambiguous = match self.sort(update) {
+      Err(OrderingError::Ambiguous(pages)) => Some(pages),
+      _ => None,
+    };
+
+    Ok(Explanation {
+      violations: self.violations(update),
+      swaps: minimal_swaps(update, &corrected),
+      moves: minimal_moves(update, &corrected),
+      corrected,
+      ambiguous,
+    })
+  }
+
+  fn must_precede(&self, page: &u32, other: &u32) -> bool {
+    self.successors.get(page).is_some_and(|s| s.contains(other))
+  }
+
+  fn successors_within<'a>(&'a self, page: &u32, pages: &'a HashSet<u32>) -> impl Iterator<Item = u32> + 'a {
+    self
+      .successors
+      .get(page)
+      .into_iter()
+      .flatten()
+      .filter(|next| pages.contains(next))
+      .copied()
+  }
+
+  fn find_cycle(&self, pages: &HashSet<u32>) -> Option<Vec<u32>> {
+    fn visit(
+      ordering: &PageOrdering,
+      page: u32,
+      pages: &HashSet<u32>,
+      path: &mut Vec<u32>,
+      done: &mut HashSet<u32>,
+    ) -> Option<Vec<u32>> {
+      if let Some(start) = path.iter().position(|&p| p == page) {
+        return Some(path[start..].to_vec());
+      }
+      if done.contains(&page) {
+        return None;
+      }
+
+      path.push(page);
+      for next in ordering.successors_within(&page, pages).sorted() {
+        if let Some(cycle) = visit(ordering, next, pages, path, done) {
+          return Some(cycle);
+        }
+      }
+      path.pop();
+      done.insert(page);
+
+      None
+    }
+
+    let mut done: HashSet<u32> = HashSet::new();
+
+    pages.iter().sorted().find_map(|&page| {
+      let cycle = visit(self, page, pages, &mut Vec::new(), &mut done)?;
+      let smallest = cycle.iter().position_min().unwrap();
+      Some(cycle[smallest..].iter().chain(cycle[..smallest].iter()).copied().collect())
+    })
+  }
+}
+
+fn minimal_swaps(update: &[u32], target: &[u32]) -> Vec<(usize, usize)> {
+  let mut current = update.to_vec();
+  let mut swaps: Vec<(usize, usize)> = Vec::new();
+
+  for i in 0..current.len() {
+    if current[i] != target[i] {
+      let j = current.iter().position(|&p| p == target[i]).unwrap();
+      current.swap(i, j);
+      swaps.push((i, j));
+    }
   }
 
-  corrected
+  swaps
 }
 
-fn values_except<'a>(e: &u32, values: &'a Vec<&u32>) -> Vec<&'a u32> {
-  values
+fn minimal_moves(update: &[u32], target: &[u32]) -> Vec<PageMove> {
+  let ranks = update
     .iter()
-    .filter(|&&v| v != e)
-    .map(|x| *x)
-    .collect::<Vec<&u32>>()
+    .map(|page| target.iter().position(|p| p == page).unwrap())
+    .collect_vec();
+  let kept: HashSet<usize> = longest_increasing_subsequence(&ranks).into_iter().collect();
+
+  let mut current = update.to_vec();
+  let mut moves: Vec<PageMove> = Vec::new();
+
+  for (rank, page) in target.iter().enumerate().filter(|(rank, _)| !kept.contains(rank)) {
+    let from = current.iter().position(|p| p == page).unwrap();
+    current.remove(from);
+    let to = match rank {
+      0 => 0,
+      _ => current.iter().position(|p| *p == target[rank - 1]).unwrap() + 1,
+    };
+    current.insert(to, *page);
+    moves.push(PageMove { page: *page, from, to });
+  }
+
+  moves
+}
+
+fn longest_increasing_subsequence(values: &[usize]) -> Vec<usize> {
+  let mut lengths = vec![1; values.len()];
+  let mut previous: Vec<Option<usize>> = vec![None; values.len()];
+
+  for i in 0..values.len() {
+    for j in 0..i {
+      if values[j] < values[i] && lengths[j] + 1 > lengths[i] {
+        lengths[i] = lengths[j] + 1;
+        previous[i] = Some(j);
+      }
+    }
+  }
+
+  let mut subsequence: Vec<usize> = Vec::new();
+  let mut next = lengths.iter().position_max();
+  while let Some(i) = next {
+    subsequence.push(values[i]);
+    next = previous[i];
+  }
+  subsequence.reverse();
+
+  subsequence
 }
 
-fn has_no_deps(v: &u32, on: &Vec<&u32>, graph: &HashMap<u32, Vec<u32>>) -> bool {
-  graph
-    .get(v)
-    .map(|successors| on.iter().all(|o| !successors.contains(o)))
-    .unwrap_or(true)
+fn parse_rule(line: &str) -> Rule {
+  line
+    .split_once('|')
+    .map(|x| (x.0.trim().parse::<u32>().unwrap(), x.1.trim().parse::<u32>().unwrap()))
+    .unwrap()
 }
 
-fn make_rules_graph(rules: &Vec<Rule>) -> HashMap<u32, Vec<u32>> {
-  rules
+fn parse_input(lines: Vec<String>) -> (Vec<String>, Vec<Update>) {
+  fn parse_update(line: &String) -> Vec<u32> {
+    line
+      .split(',')
+      .map(|page| page.parse::<u32>().unwrap())
+      .collect()
+  }
+
+  let sections = lines
+    .split(|line| line == "")
+    .map(|ll| ll.iter().map(String::from).collect())
+    .collect::<Vec<Vec<String>>>();
+  let rules = sections.first().unwrap().clone();
+  let updates = sections
+    .last()
+    .unwrap()
     .iter()
-    .into_group_map_by(|&r| r.0)
-    .into_iter()
-    .map(|(pred, rs)| (pred, rs.iter().map(|r| r.1).collect()))
-    .collect()
+    .map(|line| parse_update(line))
+    .collect::<Vec<Update>>();
+
+  (rules, updates)
+}
+
+fn get_middle_page(update: &Update) -> &u32 {
+  update.get(update.len() / 2).unwrap()
 }
 
 #[cfg(test)]
 mod tests {
-  use std::{fs::File, io::BufReader};
-
   use crate::day05::{
-    sum_middle_page_numbers_of_correct_updates, sum_middle_page_numbers_of_corrected_updates,
+    explain_incorrect_updates, sum_middle_page_numbers_of_correct_updates,
+    sum_middle_page_numbers_of_corrected_updates, OrderingError, PageMove, PageOrdering,
   };
+  use crate::inputs::open;
 
   #[test]
   fn sample_part1_input() {
-    let mut f = BufReader::new(File::open("./src/day05/sample.input").unwrap());
+    let mut f = open(5, "sample");
     assert_eq!(sum_middle_page_numbers_of_correct_updates(&mut f), 143)
   }
 
   #[test]
   fn my_part1_input() {
-    let mut f = BufReader::new(File::open("./src/day05/my.input").unwrap());
+    let mut f = open(5, "my");
     assert_eq!(sum_middle_page_numbers_of_correct_updates(&mut f), 6034)
   }
 
   #[test]
   fn sample_part2_input() {
-    let mut f = BufReader::new(File::open("./src/day05/sample.input").unwrap());
-    assert_eq!(sum_middle_page_numbers_of_corrected_updates(&mut f), 123)
+    let mut f = open(5, "sample");
+    assert_eq!(sum_middle_page_numbers_of_corrected_updates(&mut f), Ok(123))
   }
 
   #[test]
   fn my_part2_input() {
-    let mut f = BufReader::new(File::open("./src/day05/my.input").unwrap());
-    assert_eq!(sum_middle_page_numbers_of_corrected_updates(&mut f), 6305)
+    let mut f = open(5, "my");
+    assert_eq!(sum_middle_page_numbers_of_corrected_updates(&mut f), Ok(6305))
+  }
+
+  #[test]
+  fn sample_rules_are_valid() {
+    let mut f = open(5, "sample");
+    let (rules, _) = super::parse_input(crate::read_input(&mut f));
+    assert_eq!(PageOrdering::parse(&rules).validate(), Ok(()))
+  }
+
+  #[test]
+  fn my_rules_contain_cycle() {
+    let mut f = open(5, "my");
+    let (rules, _) = super::parse_input(crate::read_input(&mut f));
+    assert!(matches!(PageOrdering::parse(&rules).validate(), Err(OrderingError::Cycle(_))))
+  }
+
+  #[test]
+  fn cycle_is_reported_from_smallest_page() {
+    let ordering = PageOrdering::new(&[(3, 1), (1, 2), (2, 3), (3, 4)]);
+    assert_eq!(ordering.validate(), Err(OrderingError::Cycle(vec![1, 2, 3])));
+    assert_eq!(ordering.sort(&[4, 3, 2, 1]), Err(OrderingError::Cycle(vec![1, 2, 3])));
+    assert_eq!(ordering.sort(&[4, 3]), Ok(vec![3, 4]));
+  }
+
+  #[test]
+  fn ambiguous_order_is_reported() {
+    let ordering = PageOrdering::new(&[(1, 2), (1, 3), (3, 4)]);
+    assert_eq!(ordering.sort(&[4, 3, 2, 1]), Err(OrderingError::Ambiguous(vec![2, 3])));
+    assert_eq!(ordering.sort(&[4, 3, 1]), Ok(vec![1, 3, 4]));
+    assert_eq!(ordering.correct(&[4, 3, 2, 1]), Ok(vec![1, 3, 4, 2]));
+    assert_eq!(
+      sum_middle_page_numbers_of_corrected_updates(&mut "1|2\n1|3\n3|4\n\n4,3,2,1\n".as_bytes()),
+      Err(OrderingError::Ambiguous(vec![2, 3]))
+    );
+    assert_eq!(
+      sum_middle_page_numbers_of_corrected_updates(&mut "1|2\n2|1\n\n2,1\n".as_bytes()),
+      Err(OrderingError::Cycle(vec![1, 2]))
+    );
+  }
+
+  #[test]
+  fn sample_update_violations() {
+    let mut f = open(5, "sample");
+    let (rules, _) = super::parse_input(crate::read_input(&mut f));
+    let explanation = PageOrdering::parse(&rules).explain(&[97, 13, 75, 29, 47]).unwrap();
+
+    assert_eq!(
+      explanation.violations.iter().map(|v| v.rule.as_str()).collect::<Vec<&str>>(),
+      vec!["75|13", "29|13", "47|13", "47|29"]
+    );
+    assert_eq!(explanation.violations[0].before_position, 2);
+    assert_eq!(explanation.violations[0].after_position, 1);
+    assert_eq!(explanation.corrected, vec![97, 75, 47, 29, 13]);
+    assert_eq!(explanation.swaps, vec![(1, 2), (2, 4)]);
+    assert_eq!(
+      explanation.moves,
+      vec![PageMove { page: 29, from: 3, to: 4 }, PageMove { page: 13, from: 1, to: 4 }]
+    );
+  }
+
+  #[test]
+  fn my_input_corrections_are_minimal() {
+    let mut f = open(5, "my");
+    let (rules, updates) = super::parse_input(crate::read_input(&mut f));
+    let ordering = PageOrdering::parse(&rules);
+
+    for update in updates.iter().filter(|u| !ordering.is_correctly_ordered(u)) {
+      let explanation = ordering.explain(update).unwrap();
+      assert!(!explanation.violations.is_empty());
+
+      let mut swapped = update.clone();
+      for (i, j) in explanation.swaps.iter() {
+        swapped.swap(*i, *j);
+      }
+      assert_eq!(swapped, explanation.corrected);
+
+      let mut moved = update.clone();
+      for m in explanation.moves.iter() {
+        let page = moved.remove(m.from);
+        moved.insert(m.to, page);
+      }
+      assert_eq!(moved, explanation.corrected);
+    }
+  }
+
+  #[test]
+  fn sample_explanation_report() {
+    let mut f = open(5, "sample");
+    let report = explain_incorrect_updates(&mut f);
+
+    assert!(report.starts_with("75,97,47,61,53: 1 violated rule(s), corrected to 97,75,47,61,53\n"));
+    assert!(report.contains("  rule 97|75 broken: 97 at position 1 comes after 75 at position 0\n"));
+    assert_eq!(report.matches("violated rule(s)").count(), 3);
+    assert!(!report.contains("\n\n"));
+    assert!(report.contains("\n61,13,29: 1 violated rule(s)"));
+  }
+
+  #[test]
+  fn ambiguous_update_is_explained_with_note_and_original_rule_text() {
+    let report = explain_incorrect_updates(&mut "1 | 2\n1|3\n3|4\n\n4,3,2,1\n".as_bytes());
+
+    assert!(report.starts_with("4,3,2,1: 3 violated rule(s), corrected to 1,3,4,2\n"));
+    assert!(report.contains("  rule 1 | 2 broken: 1 at position 3 comes after 2 at position 2\n"));
+    assert!(report.ends_with("  note: rules do not order pages 2,3, kept in their original order\n"));
+    assert!(!report.contains("cannot be corrected"));
   }
 }
@@ -1,121 +1,403 @@
 use std::{collections::HashMap, io::BufRead};
 
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
 use crate::{read_input, CartesianGrid, Coords, ICoords};
 
 pub fn count_positions_visited_by_guard(input: &mut dyn BufRead) -> usize {
-  let mut guard = Guard::new(CartesianGrid::from(read_input(input)));
-  while !guard.has_left_mapped_area() {
-    guard.make_move()
-  }
-  guard.count_visited_positions()
+  let lab = Lab::new(&CartesianGrid::from(read_input(input)));
+  lab.patrol().len()
 }
 
 pub fn count_possible_loop_obstructions(input: &mut dyn BufRead) -> usize {
-  println!("w000");
-  let source_map = CartesianGrid::from(read_input(input));
-  println!("wf0");
-  let candidates = source_map.coords().iter()
-    .filter(|c| *source_map.get(c) == '.')
-    .map(|c| *c)
-    .collect::<Vec<Coords>>();
-  println!("wf1");
-  let mut count = 0;
-  for candidate in candidates {
-    println!("candidate {:?}", candidate);
-    let mut candidate_map = source_map.clone();
-    candidate_map.set(&candidate, '#');
-    let mut guard = Guard::new(candidate_map);
-    while !guard.has_left_mapped_area() && !guard.has_entered_into_loop() {
-      guard.make_move()
-    }
+  let lab = Lab::new(&CartesianGrid::from(read_input(input)));
+  let mut seen = vec![0; lab.obstacles.len() * DIRECTIONS.len()];
 
-    if guard.has_entered_into_loop() {
-      count += 1
-    }
+  lab
+    .patrol()
+    .iter()
+    .skip(1)
+    .zip(1..)
+    .filter(|(step, stamp)| lab.loops_with_obstruction(step, &mut seen, *stamp))
+    .count()
+}
+
+#[cfg(feature = "rayon")]
+pub fn count_possible_loop_obstructions_in_parallel(input: &mut dyn BufRead) -> usize {
+  let lab = Lab::new(&CartesianGrid::from(read_input(input)));
+  let states = lab.obstacles.len() * DIRECTIONS.len();
+
+  lab
+    .patrol()
+    .par_iter()
+    .skip(1)
+    .map_init(
+      || (vec![0; states], 0),
+      |(seen, stamp), step| {
+        *stamp += 1;
+        lab.loops_with_obstruction(step, seen, *stamp)
+      },
+    )
+    .filter(|&loops| loops)
+    .count()
+}
+
+pub fn trace_patrol(input: &mut dyn BufRead) -> PatrolTrace {
+  let lab = Lab::new(&CartesianGrid::from(read_input(input)));
+  lab.trace(None)
+}
+
+pub fn trace_patrol_with_obstruction(input: &mut dyn BufRead, obstruction: Coords) -> Option<PatrolTrace> {
+  let lab = Lab::new(&CartesianGrid::from(read_input(input)));
+
+  if obstruction.0 >= lab.width || obstruction.1 >= lab.height {
+    return None;
   }
-  count
+
+  Some(lab.trace(Some(obstruction.1 * lab.width + obstruction.0)))
 }
 
-struct Guard {
-  grid: CartesianGrid<char>,
-  position: Coords,
-  direction: Direction,
-  visited_positions: HashMap<Coords, HashMap<Direction, usize>>
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Heading {
+  Up,
+  Right,
+  Down,
+  Left,
 }
 
-type Direction = ICoords;
+impl Heading {
+  fn from_direction(direction: usize) -> Heading {
+    [Heading::Up, Heading::Right, Heading::Down, Heading::Left][direction]
+  }
 
-impl Guard {
-  fn new(grid: CartesianGrid<char>) -> Guard {
-    let starting_position = grid.find_one_coords('^').unwrap();
-    let starting_direction = ICoords(0, -1);
-    Guard {
-      grid,
-      position: starting_position,
-      direction: starting_direction,
-      visited_positions: HashMap::from([(starting_position, HashMap::from([(starting_direction, 1)]))])
+  fn name(&self) -> &'static str {
+    match self {
+      Heading::Up => "up",
+      Heading::Right => "right",
+      Heading::Down => "down",
+      Heading::Left => "left",
     }
   }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TraceStep {
+  pub position: Coords,
+  pub heading: Heading,
+  pub turned: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PatrolOutcome {
+  Exit(Coords),
+  Loop { entry: usize, length: usize },
+}
+
+pub struct PatrolTrace {
+  width: usize,
+  height: usize,
+  obstacles: Vec<bool>,
+  obstruction: Option<usize>,
+  steps: Vec<TraceStep>,
+  outcome: PatrolOutcome,
+}
 
-  fn has_left_mapped_area(&self) -> bool {
-    self.grid.is_boundary(&self.position)
+impl PatrolTrace {
+  pub fn steps(&self) -> &[TraceStep] {
+    &self.steps
   }
 
-  fn make_move(&mut self) {
-    if self.move_is_possible() {
-      self.move_forward()
-    }
-    else {
-      self.turn_right()
+  pub fn outcome(&self) -> PatrolOutcome {
+    self.outcome
+  }
+
+  pub fn to_csv(&self) -> String {
+    let mut csv = String::from("step,x,y,heading,turned\n");
+    for (i, step) in self.steps.iter().enumerate() {
+      csv.push_str(&format!(
+        "{},{},{},{},{}\n",
+        i,
+        step.position.0,
+        step.position.1,
+        step.heading.name(),
+        step.turned
+      ));
     }
+    csv
   }
 
-  fn move_is_possible(&self) -> bool {
-    self.get_move_projection().filter(|p| *self.grid.get(p) != '#').is_some()
+  pub fn to_json(&self) -> String {
+    let steps = self
+      .steps
+      .iter()
+      .map(|step| {
+        format!(
+          "{{\"x\":{},\"y\":{},\"heading\":\"{}\",\"turned\":{}}}",
+          step.position.0,
+          step.position.1,
+          step.heading.name(),
+          step.turned
+        )
+      })
+      .collect::<Vec<String>>()
+      .join(",");
+
+    let outcome = match self.outcome {
+      PatrolOutcome::Exit(c) => format!("{{\"exit\":{{\"x\":{},\"y\":{}}}}}", c.0, c.1),
+      PatrolOutcome::Loop { entry, length } => format!("{{\"loop\":{{\"entry\":{},\"length\":{}}}}}", entry, length),
+    };
+
+    format!("{{\"steps\":[{}],\"outcome\":{}}}", steps, outcome)
+  }
+
+  pub fn render(&self) -> String {
+    let mut vertical = vec![false; self.obstacles.len()];
+    let mut horizontal = vec![false; self.obstacles.len()];
+    let mut turned = vec![false; self.obstacles.len()];
+
+    for step in self.steps.iter() {
+      let position = step.position.1 * self.width + step.position.0;
+      match step.heading {
+        Heading::Up | Heading::Down => vertical[position] = true,
+        Heading::Left | Heading::Right => horizontal[position] = true,
+      }
+      turned[position] |= step.turned;
+    }
+
+    let start = self.steps.first().map(|step| step.position.1 * self.width + step.position.0);
+
+    (0..self.height)
+      .map(|y| {
+        (0..self.width)
+          .map(|x| {
+            let position = y * self.width + x;
+            if self.obstacles[position] {
+              '#'
+            } else if Some(position) == self.obstruction {
+              'O'
+            } else if Some(position) == start {
+              '^'
+            } else if turned[position] || (vertical[position] && horizontal[position]) {
+              '+'
+            } else if vertical[position] {
+              '|'
+            } else if horizontal[position] {
+              '-'
+            } else {
+              '.'
+            }
+          })
+          .collect::<String>()
+      })
+      .collect::<Vec<String>>()
+      .join("\n")
   }
+}
+
+type Direction = ICoords;
+
+const DIRECTIONS: [Direction; 4] = [ICoords(0, -1), ICoords(1, 0), ICoords(0, 1), ICoords(-1, 0)];
+
+struct PatrolStep {
+  position: usize,
+  previous_position: usize,
+  direction: usize,
+}
 
-  fn get_move_projection(&self) -> Option<Coords> {
-    (self.position + self.direction).to_coords()
+struct Lab {
+  width: usize,
+  height: usize,
+  obstacles: Vec<bool>,
+  start: usize,
+  jumps: Vec<[Option<usize>; 4]>,
+}
+
+impl Lab {
+  fn new(grid: &CartesianGrid<char>) -> Lab {
+    let height = grid.grid.len();
+    let width = grid.grid.first().map_or(0, |row| row.len());
+    let obstacles = grid.coords().iter().map(|c| *grid.get(c) == '#').collect();
+    let start = grid.find_one_coords('^').unwrap();
+
+    let mut lab = Lab {
+      width,
+      height,
+      obstacles,
+      start: start.1 * width + start.0,
+      jumps: vec![],
+    };
+    lab.jumps = lab.compute_jumps();
+    lab
   }
 
-  fn move_forward(&mut self) {
-    self.position = self.get_move_projection().unwrap();
+  fn compute_jumps(&self) -> Vec<[Option<usize>; 4]> {
+    let cells = self.obstacles.len();
+    let mut jumps = vec![[None; 4]; cells];
+
+    for (d, direction) in DIRECTIONS.iter().enumerate() {
+      let towards_start = direction.0 + direction.1 < 0;
+
+      for i in 0..cells {
+        let position = if towards_start { i } else { cells - 1 - i };
+        if self.obstacles[position] {
+          continue;
+        }
 
-    self.mark_position_visited()
+        jumps[position][d] = match self.step(position, d) {
+          Some(next) if self.obstacles[next] => Some(position),
+          Some(next) => jumps[next][d],
+          None => None,
+        };
+      }
+    }
+
+    jumps
   }
 
-  fn mark_position_visited(&mut self) {
-    if !self.visited_positions.contains_key(&self.position) {
-      self.visited_positions.insert(self.position, HashMap::new());
+  fn step(&self, position: usize, direction: usize) -> Option<usize> {
+    let c = Coords::new(position % self.width, position / self.width) + DIRECTIONS[direction];
+
+    if c.0 >= 0 && c.1 >= 0 && (c.0 as usize) < self.width && (c.1 as usize) < self.height {
+      Some(c.1 as usize * self.width + c.0 as usize)
+    } else {
+      None
     }
-    if !self.visited_positions.get(&self.position).unwrap().contains_key(&self.direction) {
-      self.visited_positions.get_mut(&self.position).unwrap().insert(self.direction, 0);
+  }
+
+  fn patrol(&self) -> Vec<PatrolStep> {
+    let mut visited = vec![false; self.obstacles.len()];
+    let mut steps = vec![PatrolStep {
+      position: self.start,
+      previous_position: self.start,
+      direction: 0,
+    }];
+    let (mut position, mut direction) = (self.start, 0);
+    visited[position] = true;
+
+    while let Some(next) = self.step(position, direction) {
+      if self.obstacles[next] {
+        direction = (direction + 1) % DIRECTIONS.len();
+        continue;
+      }
+
+      if !visited[next] {
+        visited[next] = true;
+        steps.push(PatrolStep {
+          position: next,
+          previous_position: position,
+          direction,
+        });
+      }
+
+      position = next;
     }
-    let count = *self.visited_positions.get(&self.position).unwrap().get(&self.direction).unwrap();
-    self.visited_positions.get_mut(&self.position).unwrap().insert(self.direction, count + 1);
+
+    steps
   }
 
-  fn turn_right(&mut self) {
-    self.direction = ICoords(
-      -self.direction.1,
-      self.direction.0
-    )
+  fn trace(&self, obstruction: Option<usize>) -> PatrolTrace {
+    let blocked = |position: usize| self.obstacles[position] || Some(position) == obstruction;
+    let to_coords = |position: usize| Coords::new(position % self.width, position / self.width);
+
+    let (mut position, mut direction) = (self.start, 0);
+    let mut seen: HashMap<(usize, usize), usize> = HashMap::new();
+    let mut steps = vec![TraceStep {
+      position: to_coords(position),
+      heading: Heading::from_direction(direction),
+      turned: false,
+    }];
+
+    let outcome = loop {
+      if let Some(&entry) = seen.get(&(position, direction)) {
+        break PatrolOutcome::Loop {
+          entry,
+          length: steps.len() - 1 - entry,
+        };
+      }
+      seen.insert((position, direction), steps.len() - 1);
+
+      let Some(next) = self.step(position, direction) else {
+        break PatrolOutcome::Exit(to_coords(position));
+      };
+
+      let turned = blocked(next);
+      if turned {
+        direction = (direction + 1) % DIRECTIONS.len();
+      } else {
+        position = next;
+      }
+
+      steps.push(TraceStep {
+        position: to_coords(position),
+        heading: Heading::from_direction(direction),
+        turned,
+      });
+    };
+
+    if let PatrolOutcome::Loop { .. } = outcome {
+      steps.pop();
+    }
+
+    PatrolTrace {
+      width: self.width,
+      height: self.height,
+      obstacles: self.obstacles.clone(),
+      obstruction,
+      steps,
+      outcome,
+    }
   }
 
-  fn has_entered_into_loop(&self) -> bool {
-    *self.visited_positions.get(&self.position)
-      .and_then(|x| x.get(&self.direction))
-      .unwrap_or(&0) >= 2
+  fn loops_with_obstruction(&self, obstruction: &PatrolStep, seen: &mut [u32], stamp: u32) -> bool {
+    let (mut position, mut direction) = (obstruction.previous_position, obstruction.direction);
+
+    loop {
+      let state = position * DIRECTIONS.len() + direction;
+      if seen[state] == stamp {
+        return true;
+      }
+      seen[state] = stamp;
+
+      let stop = if self.is_ahead(position, direction, obstruction.position, self.jumps[position][direction]) {
+        self.step(obstruction.position, (direction + 2) % DIRECTIONS.len())
+      } else {
+        self.jumps[position][direction]
+      };
+
+      match stop {
+        Some(stop) => position = stop,
+        None => return false,
+      }
+      direction = (direction + 1) % DIRECTIONS.len();
+    }
   }
 
-  fn count_visited_positions(&self) -> usize {
-    self.visited_positions.keys().len()
+  fn is_ahead(&self, position: usize, direction: usize, target: usize, stop: Option<usize>) -> bool {
+    let (x, y) = (position % self.width, position / self.width);
+    let (tx, ty) = (target % self.width, target / self.width);
+    let stop = stop.map(|s| (s % self.width, s / self.width));
+
+    match direction {
+      0 => tx == x && ty < y && stop.is_none_or(|(_, sy)| ty >= sy),
+      1 => ty == y && tx > x && stop.is_none_or(|(sx, _)| tx <= sx),
+      2 => tx == x && ty > y && stop.is_none_or(|(_, sy)| ty <= sy),
+      _ => ty == y && tx < x && stop.is_none_or(|(sx, _)| tx >= sx),
+    }
   }
 }
 
 #[cfg(test)]
 mod tests {
-  use crate::{day06::{count_positions_visited_by_guard, count_possible_loop_obstructions}, read};
+  use itertools::Itertools;
+
+  use crate::{
+    day06::{
+      count_positions_visited_by_guard, count_possible_loop_obstructions, trace_patrol, trace_patrol_with_obstruction,
+      Heading, PatrolOutcome, TraceStep,
+    },
+    read, Coords,
+  };
 
   #[test]
   fn sample_part1_input() {
@@ -136,4 +418,70 @@ mod tests {
   fn my_part2_input() {
     assert_eq!(count_possible_loop_obstructions(&mut read("./src/day06/my.input")), 1946)
   }
+
+  #[cfg(feature = "rayon")]
+  #[test]
+  fn my_part2_input_in_parallel() {
+    use crate::day06::count_possible_loop_obstructions_in_parallel;
+
+    assert_eq!(count_possible_loop_obstructions_in_parallel(&mut read("./src/day06/my.input")), 1946)
+  }
+
+  #[test]
+  fn sample_patrol_trace_exits() {
+    let trace = trace_patrol(&mut read("./src/day06/sample.input"));
+
+    assert_eq!(trace.outcome(), PatrolOutcome::Exit(Coords::new(7, 9)));
+    assert_eq!(trace.steps().iter().map(|step| step.position).unique().count(), 41);
+    assert_eq!(trace.steps().iter().filter(|step| step.turned).count(), 10);
+  }
+
+  #[test]
+  fn sample_patrol_trace_detects_loop() {
+    let trace = trace_patrol_with_obstruction(&mut read("./src/day06/sample.input"), Coords::new(3, 6)).unwrap();
+
+    assert_eq!(trace.outcome(), PatrolOutcome::Loop { entry: 0, length: 22 });
+    assert_eq!(
+      trace.render(),
+      [
+        "....#.....",
+        "....+---+#",
+        "....|...|.",
+        "..#.|...|.",
+        "....|..#|.",
+        "....|...|.",
+        ".#.O^---+.",
+        "........#.",
+        "#.........",
+        "......#...",
+      ]
+      .join("\n")
+    );
+  }
+
+  #[test]
+  fn obstruction_outside_the_lab_is_rejected() {
+    assert!(trace_patrol_with_obstruction(&mut read("./src/day06/sample.input"), Coords::new(10, 0)).is_none());
+    assert!(trace_patrol_with_obstruction(&mut read("./src/day06/sample.input"), Coords::new(0, 10)).is_none());
+  }
+
+  #[test]
+  fn sample_patrol_trace_export() {
+    let trace = trace_patrol_with_obstruction(&mut read("./src/day06/sample.input"), Coords::new(3, 6)).unwrap();
+
+    assert_eq!(
+      trace.steps()[6],
+      TraceStep {
+        position: Coords::new(4, 1),
+        heading: Heading::Right,
+        turned: true
+      }
+    );
+    assert_eq!(
+      trace.to_csv().lines().take(3).collect_vec(),
+      vec!["step,x,y,heading,turned", "0,4,6,up,false", "1,4,5,up,false"]
+    );
+    assert!(trace.to_json().starts_with(r#"{"steps":[{"x":4,"y":6,"heading":"up","turned":false},"#));
+    assert!(trace.to_json().ends_with(r#""outcome":{"loop":{"entry":0,"length":22}}}"#));
+  }
 }
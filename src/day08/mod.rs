@@ -1,64 +1,124 @@
-use std::io::BufRead;
+use std::{
+  collections::{HashMap, HashSet},
+  io::BufRead,
+  ops::RangeBounds,
+};
 
 use itertools::Itertools;
 
-use crate::{read_input, CartesianGrid, Coords, GridCoords};
+use crate::{read_input, CartesianGrid, Coords, GridCoords, ICoords};
 
 pub fn count_unique_antinode_locations(input: &mut dyn BufRead) -> usize {
-  let map = CartesianGrid::from(read_input(input));
-  map.count_unique_antinode_locations(|| (1..=1))
+  count_unique_antinode_locations_with_rule(input, &AntinodeRule::harmonics(1..=1))
 }
 
 pub fn count_unique_harmonic_antinode_locations(input: &mut dyn BufRead) -> usize {
+  count_unique_antinode_locations_with_rule(input, &AntinodeRule::collinear())
+}
+
+pub fn count_unique_antinode_locations_with_rule(input: &mut dyn BufRead, rule: &AntinodeRule) -> usize {
+  antinodes_by_frequency(input, rule)
+    .into_values()
+    .flatten()
+    .unique()
+    .count()
+}
+
+pub fn antinodes_by_frequency(input: &mut dyn BufRead, rule: &AntinodeRule) -> HashMap<char, HashSet<Coords>> {
   let map = CartesianGrid::from(read_input(input));
-  map.count_unique_antinode_locations(|| (0..))
+  map.antinodes_by_frequency(rule)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LinePoint {
+  pub near: usize,
+  pub far: usize,
+  pub spacing: usize,
+  pub between: bool,
+}
+
+pub struct AntinodeRule {
+  accepts: Box<dyn Fn(&LinePoint) -> bool>,
+}
+
+impl AntinodeRule {
+  pub fn new(accepts: impl Fn(&LinePoint) -> bool + 'static) -> Self {
+    AntinodeRule { accepts: Box::new(accepts) }
+  }
+
+  pub fn harmonics(range: impl RangeBounds<usize> + 'static) -> Self {
+    Self::new(move |p| !p.between && p.near % p.spacing == 0 && range.contains(&(p.near / p.spacing)))
+  }
+
+  pub fn distance_ratio(ratio: usize) -> Self {
+    Self::new(move |p| p.far == p.near * ratio)
+  }
+
+  pub fn collinear() -> Self {
+    Self::new(|_| true)
+  }
 }
 
 trait Map {
-  fn count_unique_antinode_locations<R>(&self, harmonics: fn() -> R) -> usize where R: IntoIterator<Item = u32>;
-  fn detect_frequency_antinodes<R>(&self, antennas: Vec<&Coords>, harmonics: fn() -> R) -> Vec<Coords> where R: IntoIterator<Item = u32>;
-  fn detect_antinodes<R>(&self, antenna1: &Coords, antenna2: &Coords, harmonics: fn() -> R) -> Vec<Coords> where R: IntoIterator<Item = u32>;
+  fn antinodes_by_frequency(&self, rule: &AntinodeRule) -> HashMap<char, HashSet<Coords>>;
+  fn detect_frequency_antinodes(&self, antennas: Vec<Coords>, rule: &AntinodeRule) -> HashSet<Coords>;
+  fn detect_antinodes(&self, antenna1: &Coords, antenna2: &Coords, rule: &AntinodeRule) -> Vec<Coords>;
 }
 
 impl Map for CartesianGrid<char> {
-  fn count_unique_antinode_locations<R>(&self, harmonics: fn() -> R) -> usize where R: IntoIterator<Item = u32> {
-    println!("kek1");
-    self.coords().iter().filter(|c| *self.get(c) != '.')
-      .into_group_map_by(|c| self.get(c))
+  fn antinodes_by_frequency(&self, rule: &AntinodeRule) -> HashMap<char, HashSet<Coords>> {
+    self.coords().into_iter().filter(|c| *self.get(c) != '.')
+      .into_group_map_by(|c| *self.get(c))
       .into_iter()
-      .flat_map(|(_, antennas)| self.detect_frequency_antinodes(antennas, harmonics))
-      .unique().collect::<Vec<Coords>>()
-      .len()
+      .map(|(frequency, antennas)| (frequency, self.detect_frequency_antinodes(antennas, rule)))
+      .collect()
   }
 
-  fn detect_frequency_antinodes<R>(&self, antennas: Vec<&Coords>, harmonics: fn() -> R) -> Vec<Coords> where R: IntoIterator<Item = u32> {
-    antennas.iter().combinations(2)
-      .map(|combination| (combination[0], combination[1]))
-      .flat_map(|(&antenna1, &antenna2)| self.detect_antinodes(antenna1, antenna2, harmonics))
+  fn detect_frequency_antinodes(&self, antennas: Vec<Coords>, rule: &AntinodeRule) -> HashSet<Coords> {
+    antennas.iter().tuple_combinations()
+      .flat_map(|(antenna1, antenna2)| self.detect_antinodes(antenna1, antenna2, rule))
       .collect()
   }
 
-  fn detect_antinodes<R>(&self, antenna1: &Coords, antenna2: &Coords, harmonics: fn() -> R) -> Vec<Coords> where R: IntoIterator<Item = u32> {
+  fn detect_antinodes(&self, antenna1: &Coords, antenna2: &Coords, rule: &AntinodeRule) -> Vec<Coords> {
     let d = antenna1 - antenna2;
-    let antenna1_antinodes = harmonics().into_iter()
-      .map(|i| i as isize)
-      .map(|i| antenna1 + d * i)
-      .take_while(|c| c.in_grid(self))
-      .flat_map(|c| c.to_coords());
-    let antenna2_antinodes = harmonics().into_iter()
-      .map(|i| i as isize)
-      .map(|i| antenna2 - d * i)
-      .take_while(|c| c.in_grid(self))
-      .flat_map(|c| c.to_coords());
-
-    antenna1_antinodes.chain(antenna2_antinodes)
+    let spacing = gcd(d.0.unsigned_abs(), d.1.unsigned_abs());
+    let step = ICoords(d.0 / spacing as isize, d.1 / spacing as isize);
+    let spacing = spacing as isize;
+
+    let forward = (0..).map(|t: isize| (t, antenna1 + step * t)).take_while(|(_, c)| c.in_grid(self));
+    let backward = (1..).map(|t: isize| (-t, antenna1 + step * -t)).take_while(|(_, c)| c.in_grid(self));
+
+    forward.chain(backward)
+      .filter(|(t, _)| {
+        let (to1, to2) = (t.unsigned_abs(), (t + spacing).unsigned_abs());
+        (rule.accepts)(&LinePoint {
+          near: to1.min(to2),
+          far: to1.max(to2),
+          spacing: spacing as usize,
+          between: -spacing < *t && *t < 0,
+        })
+      })
+      .flat_map(|(_, c)| c.to_coords())
       .collect_vec()
   }
 }
 
+fn gcd(a: usize, b: usize) -> usize {
+  if b == 0 { a } else { gcd(b, a % b) }
+}
+
 #[cfg(test)]
 mod tests {
-  use crate::{day08::{count_unique_antinode_locations, count_unique_harmonic_antinode_locations}, read};
+  use std::collections::HashSet;
+
+  use crate::{
+    day08::{
+      antinodes_by_frequency, count_unique_antinode_locations, count_unique_antinode_locations_with_rule,
+      count_unique_harmonic_antinode_locations, AntinodeRule,
+    },
+    read, Coords,
+  };
 
   #[test]
   fn sample_part1_input() {
@@ -84,4 +144,42 @@ mod tests {
   fn my_part2_input() {
     assert_eq!(count_unique_harmonic_antinode_locations(&mut read("./src/day08/my.input")), 991)
   }
+
+  const NON_PRIMITIVE: &str = "a......\n.......\n.......\n...a...\n.......\n.......\n.......";
+
+  #[test]
+  fn collinear_points_between_distant_antennas() {
+    assert_eq!(
+      count_unique_antinode_locations_with_rule(&mut NON_PRIMITIVE.as_bytes(), &AntinodeRule::collinear()),
+      7
+    );
+    assert_eq!(
+      count_unique_antinode_locations_with_rule(&mut NON_PRIMITIVE.as_bytes(), &AntinodeRule::harmonics(0..)),
+      3
+    );
+    assert_eq!(count_unique_antinode_locations(&mut NON_PRIMITIVE.as_bytes()), 1);
+  }
+
+  #[test]
+  fn distance_ratio_includes_inner_points() {
+    let antinodes = antinodes_by_frequency(&mut NON_PRIMITIVE.as_bytes(), &AntinodeRule::distance_ratio(2));
+    assert_eq!(
+      antinodes[&'a'],
+      HashSet::from([Coords::new(1, 1), Coords::new(2, 2), Coords::new(6, 6)])
+    );
+  }
+
+  #[test]
+  fn custom_rule_closure() {
+    let far_only = AntinodeRule::new(|p| !p.between && p.near >= 2 * p.spacing);
+    assert_eq!(count_unique_antinode_locations_with_rule(&mut NON_PRIMITIVE.as_bytes(), &far_only), 0);
+  }
+
+  #[test]
+  fn sample_antinodes_by_frequency() {
+    let antinodes = antinodes_by_frequency(&mut read("./src/day08/sample.input"), &AntinodeRule::harmonics(1..=1));
+    assert_eq!(antinodes.len(), 2);
+    assert_eq!(antinodes[&'A'].len(), 5);
+    assert_eq!(antinodes[&'0'].len(), 10);
+  }
 }
@@ -1,79 +1,246 @@
-use std::io::BufRead;
-
-use itertools::Itertools;
+use std::{
+  cell::OnceCell,
+  collections::{HashMap, HashSet},
+  io::BufRead,
+};
 
 use crate::{CartesianGrid, Coords, read_input};
 
 pub fn sum_trailheads_scores(input: &mut dyn BufRead) -> usize {
-  let map = CartesianGrid::parse(read_input(input));
+  let map = TopographicMap::parse(read_input(input));
   map.sum_trailheads_scores()
 }
 
 pub fn sum_trailheads_ratings(input: &mut dyn BufRead) -> usize {
-  let map = CartesianGrid::parse(read_input(input));
+  let map = TopographicMap::parse(read_input(input));
   map.sum_trailheads_ratings()
 }
 
-impl CartesianGrid<i32> {
-  fn parse(lines: Vec<String>) -> Self {
-    Self {
-      grid: lines
-        .iter()
-        .map(|line| {
-          line
-            .chars()
-            .into_iter()
-            .map(|c| c.to_digit(10).map(|v| v as i32).unwrap_or(-1))
-            .collect()
-        })
-        .collect::<Vec<Vec<i32>>>(),
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StepRule {
+  Exact(i32),
+  AscentUpTo(i32),
+}
+
+impl StepRule {
+  fn allows(&self, from: i32, to: i32) -> bool {
+    match self {
+      StepRule::Exact(step) => to - from == *step,
+      StepRule::AscentUpTo(step) => to > from && to - from <= *step,
+    }
+  }
+
+  fn direction(&self) -> i32 {
+    match self {
+      StepRule::Exact(step) => step.signum(),
+      StepRule::AscentUpTo(step) => step.signum().max(0),
+    }
+  }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TrailRulesError {
+  FlatStep(StepRule),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TrailRules {
+  trailhead: i32,
+  summit: i32,
+  step: StepRule,
+}
+
+impl Default for TrailRules {
+  fn default() -> Self {
+    TrailRules::new(0, 9, StepRule::Exact(1)).unwrap()
+  }
+}
+
+impl TrailRules {
+  pub fn new(trailhead: i32, summit: i32, step: StepRule) -> Result<Self, TrailRulesError> {
+    if step.direction() == 0 {
+      return Err(TrailRulesError::FlatStep(step));
+    }
+
+    Ok(TrailRules { trailhead, summit, step })
+  }
+}
+
+pub struct TopographicMap {
+  grid: CartesianGrid<i32>,
+  rules: TrailRules,
+  counts: OnceCell<HashMap<Coords, usize>>,
+}
+
+impl TopographicMap {
+  pub fn parse(lines: Vec<String>) -> Self {
+    TopographicMap {
+      grid: CartesianGrid::parse(lines),
+      rules: TrailRules::default(),
+      counts: OnceCell::new(),
     }
   }
 
-  fn sum_trailheads_scores(&self) -> usize {
-    self.find_hiking_paths().iter().unique().count()
+  pub fn from_heights(heights: Vec<Vec<i32>>) -> Self {
+    TopographicMap {
+      grid: CartesianGrid { grid: heights },
+      rules: TrailRules::default(),
+      counts: OnceCell::new(),
+    }
+  }
+
+  pub fn with_rules(self, rules: TrailRules) -> Self {
+    TopographicMap {
+      rules,
+      counts: OnceCell::new(),
+      ..self
+    }
+  }
+
+  pub fn sum_trailheads_scores(&self) -> usize {
+    self
+      .trailheads()
+      .iter()
+      .map(|h| self.reachable_summits(*h).len())
+      .sum()
+  }
+
+  pub fn sum_trailheads_ratings(&self) -> usize {
+    let counts = self.path_counts();
+
+    self
+      .trailheads()
+      .iter()
+      .map(|h| counts[h])
+      .sum()
   }
 
-  fn sum_trailheads_ratings(&self) -> usize {
-    self.find_hiking_paths().len()
+  pub fn trailheads(&self) -> Vec<Coords> {
+    self
+      .grid
+      .coords()
+      .into_iter()
+      .filter(|c| *self.grid.get(c) == self.rules.trailhead)
+      .collect()
   }
 
-  fn find_hiking_paths(&self) -> Vec<(Coords, Coords)> {
-    let mut to_visit: Vec<Coords> = Vec::new();
-    let mut paths: Vec<(Coords, Coords)> = Vec::new();
+  pub fn rating(&self, trailhead: Coords) -> Option<usize> {
+    self
+      .path_counts()
+      .get(&trailhead)
+      .copied()
+      .filter(|_| *self.grid.get(&trailhead) == self.rules.trailhead)
+  }
 
-    for h in self.find_trailheads() {
-      to_visit.push(h);
+  pub fn path_counts(&self) -> &HashMap<Coords, usize> {
+    self.counts.get_or_init(|| self.count_paths())
+  }
+
+  fn count_paths(&self) -> HashMap<Coords, usize> {
+    let mut counts: HashMap<Coords, usize> = HashMap::new();
+    let mut coords = self.grid.coords();
+    coords.sort_by_key(|c| -self.grid.get(c) * self.rules.step.direction());
 
-      while let Some(c) = to_visit.pop() {
-        for n in self
-          .get_neighbours(c)
+    for c in coords {
+      let count = if *self.grid.get(&c) == self.rules.summit {
+        1
+      } else {
+        self
+          .next_steps(c)
           .iter()
-          .filter(|n| *self.get(n) == self.get(&c) + 1)
-        {
-          to_visit.push(*n);
+          .map(|n| counts.get(n).copied().unwrap_or(0))
+          .sum()
+      };
+      counts.insert(c, count);
+    }
+
+    counts
+  }
+
+  pub fn reachable_summits(&self, trailhead: Coords) -> HashSet<Coords> {
+    let mut visited: HashSet<Coords> = HashSet::from([trailhead]);
+    let mut summits: HashSet<Coords> = HashSet::new();
+    let mut to_visit = vec![trailhead];
+
+    while let Some(c) = to_visit.pop() {
+      if *self.grid.get(&c) == self.rules.summit {
+        summits.insert(c);
+        continue;
+      }
 
-          if *self.get(n) == 9 {
-            paths.push((h, *n));
-          }
+      for n in self.next_steps(c) {
+        if visited.insert(n) {
+          to_visit.push(n);
         }
       }
     }
 
-    paths
+    summits
+  }
+
+  pub fn trails(&self) -> Trails<'_> {
+    Trails {
+      map: self,
+      to_visit: self.trailheads().into_iter().rev().map(|h| vec![h]).collect(),
+    }
   }
 
-  fn find_trailheads(&self) -> Vec<Coords> {
+  fn next_steps(&self, c: Coords) -> Vec<Coords> {
+    let height = *self.grid.get(&c);
+
     self
-      .coords()
-      .iter()
-      .filter(|c| *self.get(c) == 0)
-      .map(|c| *c)
-      .collect::<Vec<Coords>>()
+      .grid
+      .get_neighbours(c)
+      .into_iter()
+      .filter(|n| self.rules.step.allows(height, *self.grid.get(n)))
+      .collect()
+  }
+}
+
+pub struct Trails<'a> {
+  map: &'a TopographicMap,
+  to_visit: Vec<Vec<Coords>>,
+}
+
+impl Iterator for Trails<'_> {
+  type Item = Vec<Coords>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    while let Some(trail) = self.to_visit.pop() {
+      let last = *trail.last().unwrap();
+
+      if *self.map.grid.get(&last) == self.map.rules.summit {
+        return Some(trail);
+      }
+
+      for n in self.map.next_steps(last).into_iter().rev() {
+        let mut next = trail.clone();
+        next.push(n);
+        self.to_visit.push(next);
+      }
+    }
+
+    None
+  }
+}
+
+impl CartesianGrid<i32> {
+  fn parse(lines: Vec<String>) -> Self {
+    Self {
+      grid: lines
+        .iter()
+        .map(|line| {
+          line
+            .chars()
+            .map(|c| c.to_digit(10).map(|v| v as i32).unwrap_or(-1))
+            .collect()
+        })
+        .collect::<Vec<Vec<i32>>>(),
+    }
   }
 
   fn get_neighbours(&self, c: Coords) -> Vec<Coords> {
-    vec![
+    [
       c.add_y(1),
       c.add_x(1),
       c.sub_y(1),
@@ -88,7 +255,12 @@ impl CartesianGrid<i32> {
 
 #[cfg(test)]
 mod tests {
-  use crate::{day10::{sum_trailheads_ratings, sum_trailheads_scores}, read};
+  use std::collections::HashSet;
+
+  use crate::{
+    day10::{StepRule, TopographicMap, TrailRules, TrailRulesError, sum_trailheads_ratings, sum_trailheads_scores},
+    read, read_input, Coords,
+  };
 
   #[test]
   fn sample1_part1_input() {
@@ -177,4 +349,71 @@ mod tests {
       1289
     )
   }
+
+  #[test]
+  fn sample1_part2_path_counts() {
+    let map = TopographicMap::parse(read_input(&mut read("./src/day10/sample1.part2.input")));
+    let counts = map.path_counts();
+
+    assert_eq!(map.rating(Coords::new(5, 0)), Some(3));
+    assert_eq!(map.rating(Coords::new(2, 1)), None);
+    assert_eq!(map.rating(Coords::new(50, 50)), None);
+    assert_eq!(counts[&Coords::new(2, 1)], 1);
+    assert_eq!(counts[&Coords::new(5, 3)], 2);
+    assert_eq!(counts[&Coords::new(2, 6)], 1);
+  }
+
+  #[test]
+  fn sample_trails() {
+    let map = TopographicMap::parse(read_input(&mut read("./src/day10/sample.input")));
+    let trails: Vec<Vec<Coords>> = map.trails().collect();
+
+    assert_eq!(trails.len(), 81);
+    assert!(trails.iter().all(|trail| trail.len() == 10));
+    assert_eq!(trails.iter().collect::<HashSet<_>>().len(), 81);
+  }
+
+  #[test]
+  fn sample_reachable_summits() {
+    let map = TopographicMap::parse(read_input(&mut read("./src/day10/sample.input")));
+    let scores: Vec<usize> = map.trailheads().iter().map(|h| map.reachable_summits(*h).len()).collect();
+
+    assert_eq!(scores, vec![5, 6, 5, 3, 1, 3, 5, 3, 5]);
+  }
+
+  #[test]
+  fn custom_heights_and_step_rule() {
+    let map = TopographicMap::from_heights(vec![
+      vec![10, 12, 15],
+      vec![11, 13, 20],
+      vec![14, 16, 30],
+    ])
+    .with_rules(TrailRules::new(10, 16, StepRule::AscentUpTo(3)).unwrap());
+
+    assert_eq!(
+      map.trails().collect::<Vec<_>>(),
+      vec![
+        vec![Coords::new(0, 0), Coords::new(0, 1), Coords::new(0, 2), Coords::new(1, 2)],
+        vec![Coords::new(0, 0), Coords::new(0, 1), Coords::new(1, 1), Coords::new(1, 2)],
+        vec![Coords::new(0, 0), Coords::new(1, 0), Coords::new(1, 1), Coords::new(1, 2)],
+      ]
+    );
+    assert_eq!(map.sum_trailheads_ratings(), 3);
+    assert_eq!(map.sum_trailheads_scores(), 1);
+  }
+
+  #[test]
+  fn descending_step_rule() {
+    let map = TopographicMap::from_heights(vec![vec![9, 8, 7]]).with_rules(TrailRules::new(9, 7, StepRule::Exact(-1)).unwrap());
+
+    assert_eq!(map.trails().count(), 1);
+    assert_eq!(map.sum_trailheads_ratings(), 1);
+    assert_eq!(map.rating(Coords::new(0, 0)), Some(1));
+  }
+
+  #[test]
+  fn flat_step_rules_are_rejected() {
+    assert_eq!(TrailRules::new(0, 9, StepRule::Exact(0)).unwrap_err(), TrailRulesError::FlatStep(StepRule::Exact(0)));
+    assert!(TrailRules::new(0, 9, StepRule::AscentUpTo(0)).is_err());
+  }
 }
@@ -1,58 +1,213 @@
-use std::{collections::HashMap, io::BufRead};
+use std::{
+  collections::{hash_map::Entry, HashMap, HashSet},
+  io::BufRead,
+};
 
 use itertools::Itertools;
+use num_bigint::BigUint;
+use num_traits::One;
 
 use crate::read_input;
 
-pub fn count_stones(input: &mut dyn BufRead, blinks: u32) -> usize {
+pub fn count_stones(input: &mut dyn BufRead, blinks: u32) -> Result<usize, StoneError> {
+  count_stones_with_rules(input, blinks, &StoneRules::default())
+}
+
+pub fn count_stones_with_rules(input: &mut dyn BufRead, blinks: u32, rules: &StoneRules) -> Result<usize, StoneError> {
   let mut stones: HashMap<u64, usize> = parse_stones(read_input(input));
 
-  for _ in 0..blinks {
-    let mut tmp: HashMap<u64, isize> = HashMap::new();
-
-    for (stone, n) in stones.iter() {
-      let (left, right) = if *stone == 0 {
-        (1, None)
-      } else {
-        let s = stone.to_string();
-        if s.len() % 2 == 0 {
-          let (left, right) = s.split_at(s.len() / 2);
-          (
-            left.parse::<u64>().unwrap(),
-            Some(right.parse::<u64>().unwrap()),
-          )
-        } else {
-          (stone * 2024, None)
+  for blink in 1..=blinks {
+    let mut next: HashMap<u64, usize> = HashMap::new();
+
+    for (stone, n) in stones {
+      for s in rules.apply(stone)? {
+        let count = next.entry(s).or_insert(0);
+        *count = count.checked_add(n).ok_or(StoneError::CountOverflow(blink))?;
+      }
+    }
+
+    stones = next;
+  }
+
+  stones
+    .values()
+    .try_fold(0usize, |total, &n| total.checked_add(n))
+    .ok_or(StoneError::CountOverflow(blinks))
+}
+
+pub fn count_stones_exactly(input: &mut dyn BufRead, blinks: u32) -> Result<BigUint, StoneError> {
+  let stones = parse_stones(read_input(input));
+  let mut counter = StoneCounter::new();
+
+  stones
+    .iter()
+    .map(|(&stone, &n)| counter.count(stone, blinks).map(|c| c * n))
+    .sum()
+}
+
+pub fn count_distinct_stones_per_blink(input: &mut dyn BufRead, blinks: u32) -> Result<Vec<usize>, StoneError> {
+  let stones = parse_stones(read_input(input));
+  StoneCounter::new().distinct_stones_per_blink(&stones.keys().copied().collect_vec(), blinks)
+}
+
+#[derive(Debug, PartialEq)]
+pub enum StoneError {
+  StoneOverflow(u64, u64),
+  CountOverflow(u32),
+}
+
+#[derive(Clone, Debug)]
+pub enum Rule {
+  Replace(u64, Vec<u64>),
+  SplitEvenDigits,
+  Multiply(u64),
+}
+
+#[derive(Clone, Debug)]
+pub struct StoneRules {
+  rules: Vec<Rule>,
+}
+
+impl Default for StoneRules {
+  fn default() -> Self {
+    StoneRules::new(vec![Rule::Replace(0, vec![1]), Rule::SplitEvenDigits, Rule::Multiply(2024)])
+  }
+}
+
+impl StoneRules {
+  pub fn new(rules: Vec<Rule>) -> Self {
+    StoneRules { rules }
+  }
+
+  pub fn apply(&self, stone: u64) -> Result<Vec<u64>, StoneError> {
+    for rule in self.rules.iter() {
+      match rule {
+        Rule::Replace(from, to) if *from == stone => return Ok(to.clone()),
+        Rule::SplitEvenDigits => {
+          if let Some((left, right)) = split_digits(stone) {
+            return Ok(vec![left, right]);
+          }
+        }
+        Rule::Multiply(factor) => {
+          return stone
+            .checked_mul(*factor)
+            .map(|s| vec![s])
+            .ok_or(StoneError::StoneOverflow(stone, *factor));
+        }
+        _ => {}
+      }
+    }
+
+    Ok(vec![stone])
+  }
+}
+
+pub struct StoneCounter {
+  rules: StoneRules,
+  expansions: HashMap<u64, Vec<u64>>,
+  counts: HashMap<(u64, u32), BigUint>,
+}
+
+impl Default for StoneCounter {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl StoneCounter {
+  pub fn new() -> Self {
+    Self::with_rules(StoneRules::default())
+  }
+
+  pub fn with_rules(rules: StoneRules) -> Self {
+    StoneCounter {
+      rules,
+      expansions: HashMap::new(),
+      counts: HashMap::new(),
+    }
+  }
+
+  pub fn count(&mut self, stone: u64, blinks: u32) -> Result<BigUint, StoneError> {
+    if blinks == 0 {
+      return Ok(BigUint::one());
+    }
+    if let Some(n) = self.counts.get(&(stone, blinks)) {
+      return Ok(n.clone());
+    }
+
+    let depths = self.stones_within(stone, blinks)?;
+    let mut counts: HashMap<u64, BigUint> = depths.keys().map(|&s| (s, BigUint::one())).collect();
+
+    for b in 1..=blinks {
+      counts = depths
+        .iter()
+        .filter(|(_, depth)| **depth <= blinks - b)
+        .map(|(&s, _)| (s, self.expansions[&s].iter().map(|c| &counts[c]).sum()))
+        .collect();
+
+      self.counts.insert((stone, b), counts[&stone].clone());
+    }
+
+    Ok(counts.remove(&stone).unwrap())
+  }
+
+  pub fn distinct_stones_per_blink(&mut self, stones: &[u64], blinks: u32) -> Result<Vec<usize>, StoneError> {
+    let mut current: HashSet<u64> = stones.iter().copied().collect();
+    let mut distinct = vec![current.len()];
+
+    for _ in 0..blinks {
+      let mut next: HashSet<u64> = HashSet::new();
+      for s in current {
+        next.extend(self.expand(s)?);
+      }
+      distinct.push(next.len());
+      current = next;
+    }
+
+    Ok(distinct)
+  }
+
+  fn stones_within(&mut self, stone: u64, blinks: u32) -> Result<HashMap<u64, u32>, StoneError> {
+    let mut depths: HashMap<u64, u32> = HashMap::from([(stone, 0)]);
+    let mut layer = vec![stone];
+
+    for depth in 1..=blinks {
+      let mut next = Vec::new();
+      for s in layer {
+        for &c in self.expand(s)? {
+          if let Entry::Vacant(entry) = depths.entry(c) {
+            entry.insert(depth);
+            next.push(c);
+          }
         }
-      };
-
-      tmp
-        .entry(*stone)
-        .and_modify(|count| *count = *count - *n as isize)
-        .or_insert(-(*n as isize));
-      
-      tmp
-        .entry(left)
-        .and_modify(|count| *count = *count + *n as isize)
-        .or_insert(*n as isize);
-      
-      if let Some(r) = right {
-        tmp
-          .entry(r)
-          .and_modify(|count| *count = *count + *n as isize)
-          .or_insert(*n as isize);
       }
+      layer = next;
     }
 
-    for (stone, n) in tmp {
-      stones
-        .entry(stone)
-        .and_modify(|count| *count = (*count as isize + n) as usize)
-        .or_insert(n as usize);
+    Ok(depths)
+  }
+
+  fn expand(&mut self, stone: u64) -> Result<&Vec<u64>, StoneError> {
+    if !self.expansions.contains_key(&stone) {
+      self.expansions.insert(stone, self.rules.apply(stone)?);
     }
+    Ok(&self.expansions[&stone])
+  }
+}
+
+fn split_digits(stone: u64) -> Option<(u64, u64)> {
+  if stone == 0 {
+    return None;
   }
 
-  stones.values().sum()
+  let digits = stone.ilog10() + 1;
+
+  if digits.is_multiple_of(2) {
+    let half = 10u64.pow(digits / 2);
+    Some((stone / half, stone % half))
+  } else {
+    None
+  }
 }
 
 fn parse_stones(lines: Vec<String>) -> HashMap<u64, usize> {
@@ -72,28 +227,96 @@ fn parse_stones(lines: Vec<String>) -> HashMap<u64, usize> {
 
 #[cfg(test)]
 mod tests {
-  use crate::{day11::count_stones, read};
+  use num_bigint::BigUint;
+  use num_traits::One;
+
+  use crate::{
+    day11::{
+      count_distinct_stones_per_blink, count_stones, count_stones_exactly, count_stones_with_rules, Rule,
+      StoneCounter, StoneError, StoneRules,
+    },
+    read,
+  };
 
   #[test]
   fn sample1_part1_input() {
-    assert_eq!(count_stones(&mut read("./src/day11/sample1.input"), 1), 7);
+    assert_eq!(count_stones(&mut read("./src/day11/sample1.input"), 1), Ok(7));
   }
 
   #[test]
   fn sample2_part1_input() {
-    assert_eq!(count_stones(&mut read("./src/day11/sample2.input"), 6), 22);
+    assert_eq!(count_stones(&mut read("./src/day11/sample2.input"), 6), Ok(22));
   }
 
   #[test]
   fn my_part1_input() {
-    assert_eq!(count_stones(&mut read("./src/day11/my.input"), 25), 197357);
+    assert_eq!(count_stones(&mut read("./src/day11/my.input"), 25), Ok(197357));
   }
 
   #[test]
   fn my_part2_input() {
     assert_eq!(
       count_stones(&mut read("./src/day11/my.input"), 75),
-      234568186890978
+      Ok(234568186890978)
+    )
+  }
+
+  #[test]
+  fn my_part2_input_exactly() {
+    assert_eq!(
+      count_stones_exactly(&mut read("./src/day11/my.input"), 75),
+      Ok(BigUint::from(234568186890978u64))
+    )
+  }
+
+  #[test]
+  fn count_single_stone_for_thousands_of_blinks() {
+    let mut counter = StoneCounter::new();
+    let many = counter.count(125, 2000).unwrap();
+
+    assert_eq!(counter.count(125, 6), Ok(BigUint::from(7u32)));
+    assert_eq!(counter.count(125, 2000), Ok(many.clone()));
+    assert!(many.bits() > 1000);
+  }
+
+  #[test]
+  fn sample2_distinct_stones_per_blink() {
+    assert_eq!(
+      count_distinct_stones_per_blink(&mut read("./src/day11/sample2.input"), 6),
+      Ok(vec![2, 3, 4, 5, 8, 12, 15])
     )
   }
+
+  #[test]
+  fn sample1_with_custom_rules() {
+    let rules = StoneRules::new(vec![Rule::Replace(0, vec![0, 0]), Rule::Multiply(2)]);
+    assert_eq!(count_stones_with_rules(&mut read("./src/day11/sample1.input"), 3, &rules), Ok(12));
+  }
+
+  #[test]
+  fn stone_overflow_is_reported() {
+    let mut counter = StoneCounter::with_rules(StoneRules::new(vec![Rule::Multiply(2024)]));
+    assert_eq!(counter.count(125, 10), Err(StoneError::StoneOverflow(4245829535715328000, 2024)));
+  }
+
+  #[test]
+  fn acyclic_rules_only_blink_requested_times() {
+    let mut counter = StoneCounter::with_rules(StoneRules::new(vec![Rule::Multiply(2024)]));
+    assert_eq!(counter.count(125, 1), Ok(BigUint::one()));
+    assert_eq!(counter.count(125, 5), Ok(BigUint::one()));
+    assert_eq!(counter.count(125, 6), Err(StoneError::StoneOverflow(4245829535715328000, 2024)));
+
+    let mut counter = StoneCounter::with_rules(StoneRules::new(vec![Rule::Replace(1, vec![2, 3]), Rule::Multiply(1000)]));
+    assert_eq!(counter.count(1, 1), Ok(BigUint::from(2u32)));
+    assert_eq!(counter.count(1, 6), Ok(BigUint::from(2u32)));
+  }
+
+  #[test]
+  fn count_overflow_is_reported() {
+    let rules = StoneRules::new(vec![Rule::Replace(0, vec![0, 0])]);
+    assert_eq!(
+      count_stones_with_rules(&mut read("./src/day11/sample1.input"), 70, &rules),
+      Err(StoneError::CountOverflow(64))
+    );
+  }
 }
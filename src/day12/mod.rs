@@ -1,4 +1,4 @@
-use std::{collections::HashSet, io::BufRead};
+use std::io::BufRead;
 
 use itertools::Itertools;
 
@@ -14,50 +14,102 @@ pub fn total_discount_price(input: &mut dyn BufRead) -> usize {
   garden.total_discount_price()
 }
 
+pub fn detect_regions(input: &mut dyn BufRead) -> Vec<Region> {
+  let garden = CartesianGrid::from(read_input(input));
+  garden.label_regions()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Region {
+  id: usize,
+  plant: char,
+  plots: Vec<Coords>,
+  perimeter: usize,
+  sides: usize,
+  corners: usize,
+  bounding_box: (Coords, Coords),
+  holes: usize,
+  adjacent_regions: Vec<usize>,
+}
+
+impl Region {
+  pub fn id(&self) -> usize {
+    self.id
+  }
+
+  pub fn plant(&self) -> char {
+    self.plant
+  }
+
+  pub fn plots(&self) -> &[Coords] {
+    &self.plots
+  }
+
+  pub fn area(&self) -> usize {
+    self.plots.len()
+  }
+
+  pub fn perimeter(&self) -> usize {
+    self.perimeter
+  }
+
+  pub fn sides(&self) -> usize {
+    self.sides
+  }
+
+  pub fn corners(&self) -> usize {
+    self.corners
+  }
+
+  pub fn bounding_box(&self) -> (Coords, Coords) {
+    self.bounding_box
+  }
+
+  pub fn holes(&self) -> usize {
+    self.holes
+  }
+
+  pub fn has_holes(&self) -> bool {
+    self.holes > 0
+  }
+
+  pub fn adjacent_regions(&self) -> &[usize] {
+    &self.adjacent_regions
+  }
+
+  pub fn price(&self) -> usize {
+    self.area() * self.perimeter
+  }
+
+  pub fn discount_price(&self) -> usize {
+    self.area() * self.sides
+  }
+}
+
 trait Garden {
   fn total_price(&self) -> usize;
   fn total_discount_price(&self) -> usize;
-  fn area(&self, region: &Vec<Coords>) -> usize;
   fn plot_perimeter(&self, coords: &Coords) -> PlotPerimeter;
-  fn number_of_sides(&self, region: &Vec<Coords>) -> usize;
+  fn number_of_sides(&self, region: &[Coords]) -> usize;
   fn requires_left_fence(&self, plot: &Coords) -> bool;
   fn requires_right_fence(&self, plot: &Coords) -> bool;
   fn requires_top_fence(&self, plot: &Coords) -> bool;
   fn requires_bottom_fence(&self, plot: &Coords) -> bool;
   fn get_adjacent_plots(&self, plot: &Coords) -> Vec<Coords>;
-  fn detect_regions(&self) -> Vec<Vec<Coords>>;
 }
 
 type PlotPerimeter = (char, usize);
 
 impl Garden for CartesianGrid<char> {
   fn total_price(&self) -> usize {
-    self
-      .detect_regions()
-      .iter()
-      .map(|region| {
-        region
-          .iter()
-          .map(|plot| self.plot_perimeter(plot))
-          .collect_vec()
-      })
-      .map(|plots| plots.len() * plots.iter().map(|(_, perimeter)| perimeter).sum::<usize>())
-      .sum()
+    self.label_regions().iter().map(|region| region.price()).sum()
   }
 
   fn total_discount_price(&self) -> usize {
-    self
-      .detect_regions()
-      .iter()
-      .map(|region| self.area(region) * self.number_of_sides(region))
-      .sum()
+    self.label_regions().iter().map(|region| region.discount_price()).sum()
   }
 
-  fn area(&self, region: &Vec<Coords>) -> usize {
-      region.len()
-  }
-
-  fn number_of_sides(&self, region: &Vec<Coords>) -> usize {
+  fn number_of_sides(&self, region: &[Coords]) -> usize {
     let left_fences = region
       .iter()
       .filter(|p| self.requires_left_fence(p))
@@ -114,35 +166,6 @@ impl Garden for CartesianGrid<char> {
     !self.in_grid(&c) || self.get(&c.to_coords().unwrap()) != plot_type
   }
 
-  fn detect_regions(&self) -> Vec<Vec<Coords>> {
-    let mut visited: HashSet<Coords> = HashSet::new();
-    let mut regions: Vec<Vec<Coords>> = Vec::new();
-
-    for c in self.coords() {
-      if !visited.contains(&c) {
-        let mut to_visit: Vec<Coords> = Vec::new();
-        let mut region: Vec<Coords> = Vec::new();
-        to_visit.push(c);
-
-        while !to_visit.is_empty() {
-          let v = to_visit.remove(0);
-          visited.insert(v);
-          region.push(v);
-
-          for p in self.get_adjacent_plots(&v) {
-            if !visited.contains(&p) && !to_visit.contains(&p) {
-              to_visit.push(p);
-            }
-          }
-        }
-
-        regions.push(region);
-      }
-    }
-
-    regions
-  }
-
   fn get_adjacent_plots(&self, plot: &Coords) -> Vec<Coords> {
     let plot_type = self.get(plot);
 
@@ -150,7 +173,7 @@ impl Garden for CartesianGrid<char> {
       .get_adjacent_coords_in_bounds(*plot)
       .iter()
       .filter(|c| self.get(c) == plot_type)
-      .map(|c| *c)
+      .copied()
       .collect_vec()
   }
 
@@ -168,6 +191,83 @@ impl Garden for CartesianGrid<char> {
 }
 
 impl CartesianGrid<char> {
+  pub fn label_regions(&self) -> Vec<Region> {
+    let labels = self.label_plots();
+    let mut plots_by_label: Vec<Vec<Coords>> = Vec::new();
+
+    for c in self.coords() {
+      let label = labels[c.1][c.0];
+      if label == plots_by_label.len() {
+        plots_by_label.push(Vec::new());
+      }
+      plots_by_label[label].push(c);
+    }
+
+    plots_by_label
+      .into_iter()
+      .enumerate()
+      .map(|(id, plots)| self.describe_region(&labels, id, plots))
+      .collect()
+  }
+
+  fn label_plots(&self) -> Vec<Vec<usize>> {
+    let mut labels: Vec<Vec<Option<usize>>> = self.grid.iter().map(|row| vec![None; row.len()]).collect();
+    let mut next_label = 0;
+
+    for c in self.coords() {
+      if labels[c.1][c.0].is_some() {
+        continue;
+      }
+
+      labels[c.1][c.0] = Some(next_label);
+      let mut to_visit = vec![c];
+
+      while let Some(v) = to_visit.pop() {
+        for p in self.get_adjacent_plots(&v) {
+          if labels[p.1][p.0].is_none() {
+            labels[p.1][p.0] = Some(next_label);
+            to_visit.push(p);
+          }
+        }
+      }
+
+      next_label += 1;
+    }
+
+    labels
+      .into_iter()
+      .map(|row| row.into_iter().map(|l| l.unwrap()).collect())
+      .collect()
+  }
+
+  fn describe_region(&self, labels: &[Vec<usize>], id: usize, plots: Vec<Coords>) -> Region {
+    let bounding_box = (
+      Coords::new(plots.iter().map(|p| p.0).min().unwrap(), plots.iter().map(|p| p.1).min().unwrap()),
+      Coords::new(plots.iter().map(|p| p.0).max().unwrap(), plots.iter().map(|p| p.1).max().unwrap()),
+    );
+
+    let adjacent_regions = plots
+      .iter()
+      .flat_map(|p| self.get_adjacent_coords_in_bounds(*p))
+      .map(|c| labels[c.1][c.0])
+      .filter(|&label| label != id)
+      .unique()
+      .sorted()
+      .collect_vec();
+
+    Region {
+      id,
+      plant: *self.get(&plots[0]),
+      perimeter: plots.iter().map(|p| self.plot_perimeter(p).1).sum(),
+      sides: self.number_of_sides(&plots),
+      corners: plots.iter().map(|p| count_corners(labels, id, p)).sum(),
+      holes: count_holes(labels, id, bounding_box),
+      bounding_box,
+      adjacent_regions,
+      plots,
+    }
+  }
+
   fn get_adjacent_coords(&self, coords: Coords) -> Vec<ICoords> {
     vec![
       coords.add_y(1),
@@ -178,7 +278,7 @@ impl CartesianGrid<char> {
   }
 
   fn get_adjacent_coords_in_bounds(&self, coords: Coords) -> Vec<Coords> {
-    vec![
+    [
       coords.add_y(1),
       coords.add_x(1),
       coords.sub_y(1),
@@ -191,9 +291,73 @@ impl CartesianGrid<char> {
   }
 }
 
+fn has_label(labels: &[Vec<usize>], id: usize, c: ICoords) -> bool {
+  c.to_coords()
+    .and_then(|c| labels.get(c.1).and_then(|row| row.get(c.0)))
+    .is_some_and(|&label| label == id)
+}
+
+fn count_corners(labels: &[Vec<usize>], id: usize, plot: &Coords) -> usize {
+  [(1, 1), (1, -1), (-1, 1), (-1, -1)]
+    .iter()
+    .filter(|&&(dx, dy)| {
+      let horizontal = has_label(labels, id, plot + ICoords::new(dx, 0));
+      let vertical = has_label(labels, id, plot + ICoords::new(0, dy));
+      let diagonal = has_label(labels, id, plot + ICoords::new(dx, dy));
+
+      (!horizontal && !vertical) || (horizontal && vertical && !diagonal)
+    })
+    .count()
+}
+
+fn count_holes(labels: &[Vec<usize>], id: usize, bounding_box: (Coords, Coords)) -> usize {
+  let (min, max) = bounding_box;
+  let width = max.0 - min.0 + 3;
+  let height = max.1 - min.1 + 3;
+  let is_outside_region = |x: usize, y: usize| {
+    !has_label(labels, id, ICoords::new((min.0 + x) as isize - 1, (min.1 + y) as isize - 1))
+  };
+
+  let mut seen = vec![vec![false; width]; height];
+  let mut components = 0;
+
+  for (x, y) in (0..height).flat_map(|y| (0..width).map(move |x| (x, y))) {
+    if seen[y][x] || !is_outside_region(x, y) {
+      continue;
+    }
+
+    components += 1;
+    seen[y][x] = true;
+    let mut to_visit = vec![(x, y)];
+
+    while let Some((x, y)) = to_visit.pop() {
+      let neighbours = [
+        (x.wrapping_sub(1), y),
+        (x + 1, y),
+        (x, y.wrapping_sub(1)),
+        (x, y + 1),
+      ];
+
+      for (nx, ny) in neighbours {
+        if nx < width && ny < height && !seen[ny][nx] && is_outside_region(nx, ny) {
+          seen[ny][nx] = true;
+          to_visit.push((nx, ny));
+        }
+      }
+    }
+  }
+
+  components - 1
+}
+
 #[cfg(test)]
 mod tests {
-  use crate::{day12::{total_discount_price, total_price}, read};
+  use itertools::Itertools;
+
+  use crate::{
+    day12::{detect_regions, total_discount_price, total_price},
+    read, Coords,
+  };
 
   #[test]
   fn sample1_part1_input() {
@@ -244,4 +408,45 @@ mod tests {
   fn my_part2_input() {
     assert_eq!(total_discount_price(&mut read("./src/day12/my.input")), 908042)
   }
+
+  #[test]
+  fn sample1_regions() {
+    let regions = detect_regions(&mut read("./src/day12/sample1.input"));
+    let c = regions.iter().find(|r| r.plant() == 'C').unwrap();
+
+    assert_eq!(regions.len(), 5);
+    assert_eq!(c.area(), 4);
+    assert_eq!(c.perimeter(), 10);
+    assert_eq!(c.sides(), 8);
+    assert_eq!(c.corners(), 8);
+    assert_eq!(c.bounding_box(), (Coords::new(2, 1), Coords::new(3, 3)));
+    assert!(!c.has_holes());
+    assert_eq!(
+      c.adjacent_regions().iter().map(|&id| regions[id].plant()).sorted().collect_vec(),
+      vec!['A', 'B', 'D', 'E']
+    );
+  }
+
+  #[test]
+  fn sample2_regions_with_holes() {
+    let regions = detect_regions(&mut read("./src/day12/sample2.input"));
+    let o = regions.iter().find(|r| r.plant() == 'O').unwrap();
+
+    assert_eq!(regions.len(), 5);
+    assert_eq!(o.holes(), 4);
+    assert_eq!(o.corners(), o.sides());
+    assert_eq!(o.adjacent_regions().len(), 4);
+    assert!(regions.iter().filter(|r| r.plant() == 'X').all(|r| r.adjacent_regions() == [o.id()]));
+  }
+
+  #[test]
+  fn sample5_part2_regions_with_holes() {
+    let regions = detect_regions(&mut read("./src/day12/sample5.part2.input"));
+    let a = regions.iter().find(|r| r.plant() == 'A').unwrap();
+
+    assert_eq!(a.holes(), 2);
+    assert_eq!(a.sides(), 12);
+    assert_eq!(a.corners(), 12);
+    assert_eq!(a.bounding_box(), (Coords::new(0, 0), Coords::new(5, 5)));
+  }
 }
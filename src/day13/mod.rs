@@ -3,59 +3,226 @@ use std::io::BufRead;
 use crate::read_input;
 
 pub fn find_minimum_tokens_to_win_possible_prizes(input: &mut dyn BufRead) -> usize {
+  find_minimum_tokens_with_rules(input, &ClawRules::default().with_press_limits(Some(100), Some(100)))
+}
+
+pub fn find_minimum_tokens_to_win_possible_higher_prizes(input: &mut dyn BufRead) -> usize {
+  find_minimum_tokens_with_rules(input, &ClawRules::default().with_prize_offset(10000000000000))
+}
+
+pub fn find_minimum_tokens_with_rules(input: &mut dyn BufRead, rules: &ClawRules) -> usize {
   parse_claw_machines(read_input(input))
     .iter()
-    .flat_map(|machine| find_minimum_tokens(machine))
+    .flat_map(|machine| machine.solve(rules))
+    .map(|solution| solution.tokens)
     .sum()
 }
 
-pub fn find_minimum_tokens_to_win_possible_higher_prizes(input: &mut dyn BufRead) -> usize {
-  fn higher_prize(machine: &ClawMachine) -> ClawMachine {
-    ClawMachine {
-      button_a: machine.button_a,
-      button_b: machine.button_b,
-      prize: (10000000000000 + machine.prize.0, 10000000000000 + machine.prize.1)
+#[derive(Clone, Copy, Debug)]
+pub struct ClawRules {
+  button_a_cost: usize,
+  button_b_cost: usize,
+  button_a_limit: Option<usize>,
+  button_b_limit: Option<usize>,
+  prize_offset: usize,
+}
+
+impl Default for ClawRules {
+  fn default() -> Self {
+    ClawRules {
+      button_a_cost: 3,
+      button_b_cost: 1,
+      button_a_limit: None,
+      button_b_limit: None,
+      prize_offset: 0,
     }
   }
+}
+
+impl ClawRules {
+  pub fn with_costs(self, button_a_cost: usize, button_b_cost: usize) -> Self {
+    ClawRules { button_a_cost, button_b_cost, ..self }
+  }
 
-  parse_claw_machines(read_input(input)).iter().map(|m| higher_prize(m))
-    .flat_map(|machine| find_minimum_tokens(&machine))
-    .sum()
+  pub fn with_press_limits(self, button_a_limit: Option<usize>, button_b_limit: Option<usize>) -> Self {
+    ClawRules { button_a_limit, button_b_limit, ..self }
+  }
+
+  pub fn with_prize_offset(self, prize_offset: usize) -> Self {
+    ClawRules { prize_offset, ..self }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ClawSolution {
+  pub button_a_presses: usize,
+  pub button_b_presses: usize,
+  pub tokens: usize,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ClawMachine {
+  button_a: (usize, usize),
+  button_b: (usize, usize),
+  prize: (usize, usize),
+}
+
+impl ClawMachine {
+  pub fn new(button_a: (usize, usize), button_b: (usize, usize), prize: (usize, usize)) -> Self {
+    ClawMachine { button_a, button_b, prize }
+  }
+
+  pub fn solve(&self, rules: &ClawRules) -> Option<ClawSolution> {
+    let (a, b) = solve_linear_equations(
+      (
+        self.button_a.0 as i128,
+        self.button_b.0 as i128,
+        (self.prize.0 + rules.prize_offset) as i128,
+      ),
+      (
+        self.button_a.1 as i128,
+        self.button_b.1 as i128,
+        (self.prize.1 + rules.prize_offset) as i128,
+      ),
+      (rules.button_a_cost as i128, rules.button_b_cost as i128),
+      (rules.button_a_limit.map(|l| l as i128), rules.button_b_limit.map(|l| l as i128)),
+    )?;
+
+    Some(ClawSolution {
+      button_a_presses: a as usize,
+      button_b_presses: b as usize,
+      tokens: a as usize * rules.button_a_cost + b as usize * rules.button_b_cost,
+    })
+  }
 }
 
-fn find_minimum_tokens(machine: &ClawMachine) -> Option<usize> {
-  let (a, b) = solve_linear_equations(
-    (
-      machine.button_a.0 as f64,
-      machine.button_b.0 as f64,
-      machine.prize.0 as f64,
-    ),
-    (
-      machine.button_a.1 as f64,
-      machine.button_b.1 as f64,
-      machine.prize.1 as f64,
-    ),
-  );
-
-  if a >= 0f64 && (a as usize) as f64 == a && b >= 0f64 && (b as usize) as f64 == b {
-    Some(a as usize * 3 + b as usize)
+type Costs = (i128, i128);
+type Limits = (Option<i128>, Option<i128>);
+
+fn within_limits(x: i128, y: i128, limits: Limits) -> bool {
+  x >= 0 && y >= 0 && limits.0.is_none_or(|l| x <= l) && limits.1.is_none_or(|l| y <= l)
+}
+
+fn solve_linear_equations(
+  equation_1: (i128, i128, i128),
+  equation_2: (i128, i128, i128),
+  costs: Costs,
+  limits: Limits,
+) -> Option<(i128, i128)> {
+  let (a, b, _) = equation_1;
+  let (c, d, _) = equation_2;
+
+  if a * d - b * c != 0 {
+    solve_linear_equations_using_cramers_formula(equation_1, equation_2).filter(|&(x, y)| within_limits(x, y, limits))
   } else {
-    None
+    solve_dependent_linear_equations(equation_1, equation_2, costs, limits)
   }
 }
 
-fn solve_linear_equations(equation_1: (f64, f64, f64), equation_2: (f64, f64, f64)) -> (f64, f64) {
-  solve_linear_quations_using_cramers_formula(equation_1, equation_2)
+fn solve_linear_equations_using_cramers_formula(equation_1: (i128, i128, i128), equation_2: (i128, i128, i128)) -> Option<(i128, i128)> {
+  let (a, b, e) = equation_1;
+  let (c, d, f) = equation_2;
+
+  let determinant = a * d - b * c;
+  let x = e * d - b * f;
+  let y = a * f - e * c;
+
+  if x % determinant != 0 || y % determinant != 0 {
+    return None;
+  }
+
+  Some((x / determinant, y / determinant))
 }
 
-fn solve_linear_quations_using_cramers_formula(equation_1: (f64, f64, f64), equation_2: (f64, f64, f64)) -> (f64, f64) {
+fn solve_dependent_linear_equations(
+  equation_1: (i128, i128, i128),
+  equation_2: (i128, i128, i128),
+  costs: Costs,
+  limits: Limits,
+) -> Option<(i128, i128)> {
   let (a, b, e) = equation_1;
   let (c, d, f) = equation_2;
 
-  let x = (e * d - b * f) / (a * d - b * c);
-  let y = (a * f - e * c) / (a * d - b * c);
+  if a * f - e * c != 0 || b * f - e * d != 0 {
+    return None;
+  }
+
+  let (a, b, e) = if a != 0 || b != 0 { equation_1 } else { equation_2 };
+
+  if a == 0 && b == 0 {
+    return if e == 0 { Some((0, 0)) } else { None };
+  }
+
+  let (g, x0, y0) = extended_gcd(a, b);
+
+  if e % g != 0 {
+    return None;
+  }
+
+  // x = x0' + k * dx, y = y0' - k * dy spans every integer solution of a * x + b * y = e
+  let (x0, y0) = (x0 * (e / g), y0 * (e / g));
+  let (dx, dy) = (b / g, a / g);
+
+  let mut lo: Option<i128> = None;
+  let mut hi: Option<i128> = None;
+
+  let mut constraints = vec![(x0, dx), (y0, -dy)];
+  if let Some(limit) = limits.0 {
+    constraints.push((limit - x0, -dx));
+  }
+  if let Some(limit) = limits.1 {
+    constraints.push((limit - y0, dy));
+  }
+
+  for (c0, c1) in constraints {
+    if c1 > 0 {
+      let bound = div_ceil(-c0, c1);
+      lo = Some(lo.map_or(bound, |lo| lo.max(bound)));
+    } else if c1 < 0 {
+      let bound = div_floor(c0, -c1);
+      hi = Some(hi.map_or(bound, |hi| hi.min(bound)));
+    } else if c0 < 0 {
+      return None;
+    }
+  }
+
+  if let (Some(lo), Some(hi)) = (lo, hi)
+    && lo > hi
+  {
+    return None;
+  }
+
+  let slope = costs.0 * dx - costs.1 * dy;
+  let k = if slope > 0 { lo.or(hi) } else { hi.or(lo) }.unwrap_or(0);
+
+  Some((x0 + k * dx, y0 - k * dy))
+}
+
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+  let (mut old_r, mut r) = (a, b);
+  let (mut old_s, mut s) = (1, 0);
+  let (mut old_t, mut t) = (0, 1);
+
+  while r != 0 {
+    let q = old_r / r;
+    (old_r, r) = (r, old_r - q * r);
+    (old_s, s) = (s, old_s - q * s);
+    (old_t, t) = (t, old_t - q * t);
+  }
+
+  if old_r < 0 {
+    (-old_r, -old_s, -old_t)
+  } else {
+    (old_r, old_s, old_t)
+  }
+}
+
+fn div_floor(a: i128, b: i128) -> i128 {
+  a.div_euclid(b)
+}
 
-  (x, y)
+fn div_ceil(a: i128, b: i128) -> i128 {
+  -(-a).div_euclid(b)
 }
 
 fn parse_claw_machines(lines: Vec<String>) -> Vec<ClawMachine> {
@@ -85,15 +252,15 @@ fn parse_claw_machines(lines: Vec<String>) -> Vec<ClawMachine> {
     .collect()
 }
 
-struct ClawMachine {
-  button_a: (usize, usize),
-  button_b: (usize, usize),
-  prize: (usize, usize),
-}
-
 #[cfg(test)]
 mod tests {
-  use crate::{day13::{find_minimum_tokens_to_win_possible_higher_prizes, find_minimum_tokens_to_win_possible_prizes}, read};
+  use crate::{
+    day13::{
+      find_minimum_tokens_to_win_possible_higher_prizes, find_minimum_tokens_to_win_possible_prizes,
+      find_minimum_tokens_with_rules, ClawMachine, ClawRules, ClawSolution,
+    },
+    read,
+  };
 
   #[test]
   fn sample_part1_input() {
@@ -126,4 +293,80 @@ mod tests {
       104958599303720
     )
   }
+
+  #[test]
+  fn sample_part1_input_with_swapped_costs() {
+    assert_eq!(
+      find_minimum_tokens_with_rules(
+        &mut read("./src/day13/sample.input"),
+        &ClawRules::default().with_costs(1, 3).with_press_limits(Some(100), Some(100))
+      ),
+      80 + 40 * 3 + 38 + 86 * 3
+    )
+  }
+
+  #[test]
+  fn solution_reports_button_presses() {
+    let machine = ClawMachine::new((94, 34), (22, 67), (8400, 5400));
+    assert_eq!(
+      machine.solve(&ClawRules::default()),
+      Some(ClawSolution {
+        button_a_presses: 80,
+        button_b_presses: 40,
+        tokens: 280
+      })
+    )
+  }
+
+  #[test]
+  fn press_limits_are_enforced() {
+    let machine = ClawMachine::new((94, 34), (22, 67), (8400, 5400));
+    assert_eq!(machine.solve(&ClawRules::default().with_press_limits(Some(79), None)), None);
+    assert_eq!(machine.solve(&ClawRules::default().with_press_limits(None, Some(39))), None);
+  }
+
+  #[test]
+  fn collinear_buttons_prefer_cheaper_button() {
+    let machine = ClawMachine::new((4, 2), (2, 1), (10, 5));
+    assert_eq!(machine.solve(&ClawRules::default()).map(|s| s.tokens), Some(5))
+  }
+
+  #[test]
+  fn collinear_buttons_prefer_more_efficient_button() {
+    let machine = ClawMachine::new((12, 6), (2, 1), (26, 13));
+    assert_eq!(
+      machine.solve(&ClawRules::default()),
+      Some(ClawSolution {
+        button_a_presses: 2,
+        button_b_presses: 1,
+        tokens: 7
+      })
+    )
+  }
+
+  #[test]
+  fn collinear_buttons_respect_press_limits() {
+    let machine = ClawMachine::new((12, 6), (2, 1), (26, 13));
+    assert_eq!(
+      machine.solve(&ClawRules::default().with_press_limits(Some(1), None)),
+      Some(ClawSolution {
+        button_a_presses: 1,
+        button_b_presses: 7,
+        tokens: 10
+      })
+    );
+    assert_eq!(machine.solve(&ClawRules::default().with_press_limits(Some(1), Some(6))), None)
+  }
+
+  #[test]
+  fn collinear_buttons_without_integer_solution() {
+    let machine = ClawMachine::new((4, 2), (8, 4), (6, 3));
+    assert_eq!(machine.solve(&ClawRules::default()), None)
+  }
+
+  #[test]
+  fn collinear_buttons_without_non_negative_solution() {
+    let machine = ClawMachine::new((4, 2), (6, 3), (2, 1));
+    assert_eq!(machine.solve(&ClawRules::default()), None)
+  }
 }
@@ -1,323 +1,419 @@
-use std::io::BufRead;
+use std::{
+  collections::{HashMap, HashSet},
+  io::BufRead,
+};
 
 use itertools::Itertools;
 
 use crate::{read_input, CartesianGrid, Coords, ICoords};
 
 pub fn sum_boxes_gps_coordinates(input: &mut dyn BufRead) -> usize {
-  let lines = read_input(input);
-  let mut parts = lines.split(|line| line == "");
-  let mut warehouse = Warehouse::new(CartesianGrid::from(parts.next().unwrap().to_vec()));
-  let moves = parse_moves(parts.next().unwrap());
-
-  for m in &moves {
-    warehouse.attempt_move(m);
-  }
-
-  warehouse.get_boxes().iter().map(|c| c.0 + c.1 * 100).sum()
+  sum_boxes_gps_coordinates_with_scale(input, Scale::new(1, 1))
 }
 
 pub fn sum_scaled_up_boxes_gps_coordinates(input: &mut dyn BufRead) -> usize {
-  let lines = read_input(input);
-  let mut parts = lines.split(|line| line == "");
-  let mut warehouse =
-    Warehouse::new(CartesianGrid::from(parts.next().unwrap().to_vec())).scale_up();
-  let moves = parse_moves(parts.next().unwrap());
+  sum_boxes_gps_coordinates_with_scale(input, Scale::new(2, 1))
+}
+
+pub fn sum_boxes_gps_coordinates_with_scale(input: &mut dyn BufRead, scale: Scale) -> usize {
+  let (mut warehouse, moves) = parse_input(input, scale);
 
   for m in &moves {
     warehouse.attempt_move(m);
   }
 
-  warehouse.get_boxes().iter().map(|c| c.0 + c.1 * 100).sum()
+  warehouse.sum_boxes_gps_coordinates()
 }
 
-fn parse_moves(moves: &[String]) -> Vec<UnitVector> {
-  moves
-    .iter()
-    .flat_map(|line| {
-      line.chars().flat_map(|c| match c {
-        '^' => Ok(UnitVector::new(0, -1)),
-        '>' => Ok(UnitVector::new(1, 0)),
-        'v' => Ok(UnitVector::new(0, 1)),
-        '<' => Ok(UnitVector::new(-1, 0)),
-        _ => Err("Invalid character"),
-      })
-    })
-    .collect()
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Scale {
+  x: usize,
+  y: usize,
 }
 
-struct Warehouse {
-  grid: CartesianGrid<char>,
-  current_position: Coords,
+impl Scale {
+  pub fn new(x: usize, y: usize) -> Self {
+    Scale { x, y }
+  }
 }
 
-struct ScaledWarehouse {
-  grid: CartesianGrid<char>,
-  current_position: Coords,
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WarehouseBox {
+  position: Coords,
+  width: usize,
+  height: usize,
 }
 
-impl ScaledWarehouse {
-  fn get_boxes(&self) -> Vec<Coords> {
-    self
-      .grid
-      .coords()
-      .iter()
-      .filter(|c| *self.grid.get(c) == '[')
-      .map(|c| *c)
-      .collect()
+impl WarehouseBox {
+  pub fn position(&self) -> Coords {
+    self.position
   }
 
-  fn find_free_space(&self, direction: &UnitVector) -> Option<Coords> {
-    self
-      .get_coords_in_direction(&direction)
-      .find(|c| *self.grid.get(c) == '.')
+  pub fn width(&self) -> usize {
+    self.width
   }
 
-  fn get_coords_in_direction(&self, direction: &UnitVector) -> impl Iterator<Item = Coords> {
-    (1..)
-      .flat_map(move |i| (self.current_position + direction * i).to_coords())
-      .take_while(|c| *self.grid.get(c) == '[' || *self.grid.get(c) == ']' || *self.grid.get(c) == '.')
+  pub fn height(&self) -> usize {
+    self.height
   }
 
-  fn get_big_box_coordinates(&self, coords: Coords) -> BigBox {
-    if self.get(&coords) == '[' {
-      (coords, coords.add_x(1).to_coords().unwrap())
-    }
-    else {
-      (coords.sub_x(1).to_coords().unwrap(), coords)
-    }
+  pub fn gps_coordinate(&self) -> usize {
+    self.position.0 + self.position.1 * 100
   }
 
-  fn can_bigbox_be_pushed(&self, bigbox: BigBox, direction: &UnitVector) -> bool {
-    if (bigbox.0 + direction).to_coords().filter(|c| self.get(c) == '.').is_some() 
-      && (bigbox.1 + direction).to_coords().filter(|c| self.get(c) == '.').is_some() {
-      true
-    }
-    else if (bigbox.0 + direction).to_coords().filter(|c| self.get(c) == '#').is_some()
-      || (bigbox.1 + direction).to_coords().filter(|c| self.get(c) == '#').is_some() {
-      false
-    }
-    else {
-      let left = (bigbox.0 + direction).to_coords().unwrap();
-      let right = (bigbox.1 + direction).to_coords().unwrap();
-
-      if self.get(&left) == '[' {
-        self.can_bigbox_be_pushed(self.get_big_box_coordinates(left), direction)
-      }
-      else if self.get(&left) == ']' && self.get(&right) == '[' {
-        self.can_bigbox_be_pushed(self.get_big_box_coordinates(left), direction)
-          && self.can_bigbox_be_pushed(self.get_big_box_coordinates(right), direction)
-      }
-      else if self.get(&left) == ']' {
-        self.can_bigbox_be_pushed(self.get_big_box_coordinates(left), direction)
-      }
-      else {
-        self.can_bigbox_be_pushed(self.get_big_box_coordinates(right), direction)
-      }
-    }
+  fn cells(&self) -> impl Iterator<Item = Coords> + '_ {
+    (0..self.height)
+      .cartesian_product(0..self.width)
+      .map(|(dy, dx)| Coords::new(self.position.0 + dx, self.position.1 + dy))
   }
+}
+
+pub struct WarehouseSimulator {
+  warehouse: Warehouse,
+  moves: Vec<UnitVector>,
+  history: Vec<MoveRecord>,
+  step: usize,
+}
 
-  fn push_bigbox(&mut self, bigbox: BigBox, direction: &UnitVector) {
-    let mut boxes: Vec<BigBox> = Vec::new();
-    let mut to_visit: Vec<Coords> = Vec::new();
-    let left = (bigbox.0 + direction).to_coords().unwrap();
-    let right = (bigbox.1 + direction).to_coords().unwrap();
-    to_visit.push(left);
-    to_visit.push(right);
-    boxes.push(bigbox);
+#[derive(Clone, Debug, PartialEq)]
+pub struct MoveRecord {
+  pub direction: char,
+  pub robot_before: Coords,
+  pub robot_after: Coords,
+  pub pushed_boxes: Vec<Coords>,
+  moved: Vec<usize>,
+}
 
-    while let Some(c) = to_visit.pop() {
-      if self.get(&c) != '.' {
-        let bb = self.get_big_box_coordinates(c);
-        to_visit.push((bb.0 + direction).to_coords().unwrap());
-        to_visit.push((bb.1 + direction).to_coords().unwrap());
-        boxes.push(bb);
-      }
-    }
+impl WarehouseSimulator {
+  pub fn new(input: &mut dyn BufRead) -> Self {
+    Self::with_scale(input, Scale::new(1, 1))
+  }
 
-    for b in boxes.iter().unique().rev() {
-      self.grid.set(&(b.0 + direction).to_coords().unwrap(), '[');
-      self.grid.set(&(b.1 + direction).to_coords().unwrap(), ']');
-      self.grid.set(&b.0, '.');
-      self.grid.set(&b.1, '.');
-    }
+  pub fn scaled_up(input: &mut dyn BufRead) -> Self {
+    Self::with_scale(input, Scale::new(2, 1))
   }
-}
 
-trait Robot {
-  fn attempt_move(&mut self, direction: &UnitVector) {
-    if self.can_make_any_move(direction) {
-      if self.can_move(direction) {
-        self.make_move(direction)
-      } else {
-        self.try_push(direction);
-      }
+  pub fn with_scale(input: &mut dyn BufRead, scale: Scale) -> Self {
+    let (warehouse, moves) = parse_input(input, scale);
+
+    WarehouseSimulator {
+      warehouse,
+      moves,
+      history: Vec::new(),
+      step: 0,
     }
   }
 
-  fn can_make_any_move(&self, direction: &UnitVector) -> bool {
-    self.get_field_in_direction(direction) != '#'
+  pub fn step(&self) -> usize {
+    self.step
   }
 
-  fn can_move(&self, direction: &UnitVector) -> bool {
-    self.get_field_in_direction(direction) == '.'
+  pub fn len(&self) -> usize {
+    self.moves.len()
   }
 
-  fn get_field_in_direction(&self, direction: &UnitVector) -> char {
-    self.get(&(self.current_position() + direction).to_coords().unwrap())
+  pub fn is_empty(&self) -> bool {
+    self.moves.is_empty()
   }
 
-  fn make_move(&mut self, direction: &UnitVector) {
-    self.set(&self.current_position(), '.');
-    let next_position = (self.current_position() + direction).to_coords().unwrap();
-    self.set(&next_position, '@');
-    self.set_current_position(next_position);
+  pub fn history(&self) -> &[MoveRecord] {
+    &self.history[..self.step]
   }
 
-  fn current_position(&self) -> Coords;
-  fn set_current_position(&mut self, coords: Coords);
-  fn get(&self, coords: &Coords) -> char;
-  fn set(&mut self, coords: &Coords, value: char);
-  fn try_push(&mut self, direction: &UnitVector);
-}
+  pub fn boxes(&self) -> &[WarehouseBox] {
+    &self.warehouse.boxes
+  }
 
-impl Warehouse {
-  fn new(grid: CartesianGrid<char>) -> Self {
-    let starting_position = grid.find_one_coords('@').unwrap();
+  pub fn robot(&self) -> Coords {
+    self.warehouse.robot
+  }
 
-    Warehouse {
-      grid: grid,
-      current_position: starting_position,
+  pub fn step_forward(&mut self) -> Option<&MoveRecord> {
+    if self.step >= self.moves.len() {
+      return None;
     }
-  }
 
-  fn scale_up(&self) -> ScaledWarehouse {
-    let scaled_up_grid: CartesianGrid<char> = CartesianGrid {
-      grid: self
-        .grid
-        .grid
+    let direction = self.moves[self.step];
+
+    if self.step == self.history.len() {
+      let robot_before = self.warehouse.robot;
+      let moved = self.warehouse.attempt_move(&direction).unwrap_or_default();
+      let pushed_boxes = moved
         .iter()
-        .map(|row| {
-          row
-            .iter()
-            .flat_map(|c| match c {
-              '#' => vec!['#', '#'],
-              'O' => vec!['[', ']'],
-              '.' => vec!['.', '.'],
-              '@' => vec!['@', '.'],
-              _ => vec![],
-            })
-            .collect_vec()
-        })
-        .collect::<Vec<Vec<char>>>(),
-    };
+        .map(|&i| self.warehouse.boxes[i].position)
+        .map(|c| (c + opposite(&direction)).to_coords().unwrap())
+        .sorted_by_key(|c| (c.1, c.0))
+        .collect();
+
+      self.history.push(MoveRecord {
+        direction: direction_symbol(&direction),
+        robot_before,
+        robot_after: self.warehouse.robot,
+        pushed_boxes,
+        moved,
+      });
+    } else {
+      let record = &self.history[self.step];
+      self.warehouse.shift_boxes(&record.moved, &direction);
+      self.warehouse.robot = record.robot_after;
+    }
 
-    let starting_position = scaled_up_grid.find_one_coords('@').unwrap();
+    self.step += 1;
+    self.history.get(self.step - 1)
+  }
 
-    ScaledWarehouse {
-      grid: scaled_up_grid,
-      current_position: starting_position,
+  pub fn undo(&mut self) -> Option<&MoveRecord> {
+    if self.step == 0 {
+      return None;
     }
-  }
 
-  fn get_boxes(&self) -> Vec<Coords> {
-    self
-      .grid
-      .coords()
-      .iter()
-      .filter(|c| *self.grid.get(c) == 'O')
-      .map(|c| *c)
-      .collect()
+    self.step -= 1;
+    let record = &self.history[self.step];
+    self.warehouse.shift_boxes(&record.moved, &opposite(&self.moves[self.step]));
+    self.warehouse.robot = record.robot_before;
+
+    Some(record)
   }
 
-  fn find_free_space(&self, direction: &UnitVector) -> Option<Coords> {
-    self
-      .get_coords_in_direction(&direction)
-      .find(|c| *self.grid.get(c) == '.')
+  pub fn redo(&mut self) -> Option<&MoveRecord> {
+    if self.step < self.history.len() {
+      self.step_forward()
+    } else {
+      None
+    }
   }
 
-  fn get_coords_in_direction(&self, direction: &UnitVector) -> impl Iterator<Item = Coords> {
-    (1..)
-    .flat_map(move |i| (self.current_position + direction * i).to_coords())
-    .take_while(|c| *self.grid.get(c) == 'O' || *self.grid.get(c) == '.')
+  pub fn jump_to(&mut self, step: usize) {
+    let step = step.min(self.moves.len());
+
+    while self.step < step {
+      self.step_forward();
+    }
+    while self.step > step {
+      self.undo();
+    }
   }
-}
 
-impl Robot for Warehouse {
-  fn current_position(&self) -> Coords {
-    self.current_position
+  pub fn render(&self) -> String {
+    self.warehouse.render()
   }
 
-  fn set_current_position(&mut self, coords: Coords) {
-    self.current_position = coords
+  pub fn replay(&mut self, mut renderer: impl FnMut(usize, &str)) {
+    self.jump_to(0);
+    renderer(0, &self.render());
+
+    while self.step_forward().is_some() {
+      renderer(self.step, &self.render());
+    }
   }
 
-  fn get(&self, coords: &Coords) -> char {
-    *self.grid.get(coords)
+  pub fn replay_to_terminal(&mut self, frame_delay: std::time::Duration) {
+    self.replay(|step, frame| {
+      println!("\x1b[2J\x1b[HStep {}\n{}", step, frame);
+      std::thread::sleep(frame_delay);
+    });
   }
 
-  fn set(&mut self, coords: &Coords, value: char) {
-    self.grid.set(coords, value)
+  pub fn sum_boxes_gps_coordinates(&self) -> usize {
+    self.warehouse.sum_boxes_gps_coordinates()
   }
+}
 
-  fn try_push(&mut self, direction: &UnitVector) {
-    if let Some(free_space) = self.find_free_space(direction) {
-      self.grid.set(&free_space, 'O');
-      self.make_move(direction);
-    }
+fn direction_symbol(direction: &UnitVector) -> char {
+  match (direction.0, direction.1) {
+    (0, -1) => '^',
+    (1, 0) => '>',
+    (0, 1) => 'v',
+    _ => '<',
   }
 }
 
-impl Robot for ScaledWarehouse {
-  fn current_position(&self) -> Coords {
-    self.current_position
+fn opposite(direction: &UnitVector) -> UnitVector {
+  UnitVector::new(-direction.0, -direction.1)
+}
+
+fn parse_input(input: &mut dyn BufRead, scale: Scale) -> (Warehouse, Vec<UnitVector>) {
+  let lines = read_input(input);
+  let mut parts = lines.split(|line| line.is_empty());
+  let warehouse = Warehouse::new(parts.next().unwrap(), scale);
+  let moves = parse_moves(parts.next().unwrap());
+
+  (warehouse, moves)
+}
+
+fn parse_moves(moves: &[String]) -> Vec<UnitVector> {
+  moves
+    .iter()
+    .flat_map(|line| {
+      line.chars().flat_map(|c| match c {
+        '^' => Ok(UnitVector::new(0, -1)),
+        '>' => Ok(UnitVector::new(1, 0)),
+        'v' => Ok(UnitVector::new(0, 1)),
+        '<' => Ok(UnitVector::new(-1, 0)),
+        _ => Err("Invalid character"),
+      })
+    })
+    .collect()
+}
+
+struct Warehouse {
+  floor: CartesianGrid<char>,
+  occupancy: HashMap<Coords, usize>,
+  boxes: Vec<WarehouseBox>,
+  robot: Coords,
+}
+
+impl Warehouse {
+  fn new(lines: &[String], scale: Scale) -> Self {
+    let mut floor: Vec<Vec<char>> = Vec::new();
+    let mut boxes: Vec<WarehouseBox> = Vec::new();
+    let mut robot = Coords::new(0, 0);
+
+    for (y, line) in lines.iter().enumerate() {
+      let row = line.chars().collect_vec();
+      let mut x = 0;
+
+      while x < row.len() {
+        let width = match row[x] {
+          '[' => row[x..].iter().position(|&c| c == ']').unwrap() + 1,
+          _ => 1,
+        };
+
+        match row[x] {
+          'O' | '[' => boxes.push(WarehouseBox {
+            position: Coords::new(x * scale.x, y * scale.y),
+            width: width * scale.x,
+            height: scale.y,
+          }),
+          '@' => robot = Coords::new(x * scale.x, y * scale.y),
+          _ => {}
+        }
+
+        x += width;
+      }
+
+      let scaled_row = row
+        .iter()
+        .flat_map(|&c| std::iter::repeat_n(if c == '#' { '#' } else { '.' }, scale.x))
+        .collect_vec();
+      floor.extend(std::iter::repeat_n(scaled_row, scale.y));
+    }
+
+    let mut warehouse = Warehouse {
+      floor: CartesianGrid { grid: floor },
+      occupancy: HashMap::new(),
+      boxes,
+      robot,
+    };
+    warehouse.occupy(&(0..warehouse.boxes.len()).collect_vec());
+    warehouse
   }
 
-  fn set_current_position(&mut self, coords: Coords) {
-    self.current_position = coords
+  fn attempt_move(&mut self, direction: &UnitVector) -> Option<Vec<usize>> {
+    let pushed = self.boxes_to_push(direction)?;
+
+    self.shift_boxes(&pushed, direction);
+    self.robot = (self.robot + direction).to_coords().unwrap();
+
+    Some(pushed)
   }
 
-  fn get(&self, coords: &Coords) -> char {
-    *self.grid.get(coords)
+  fn boxes_to_push(&self, direction: &UnitVector) -> Option<Vec<usize>> {
+    let mut pushed: Vec<usize> = Vec::new();
+    let mut seen: HashSet<usize> = HashSet::new();
+    let mut to_visit: Vec<ICoords> = vec![self.robot + direction];
+
+    while let Some(c) = to_visit.pop() {
+      if !self.floor.in_grid(&c) {
+        return None;
+      }
+
+      let c = c.to_coords().unwrap();
+      if *self.floor.get(&c) == '#' {
+        return None;
+      }
+
+      if let Some(&i) = self.occupancy.get(&c)
+        && seen.insert(i)
+      {
+        pushed.push(i);
+        to_visit.extend(self.boxes[i].cells().map(|cell| cell + direction));
+      }
+    }
+
+    Some(pushed)
   }
 
-  fn set(&mut self, coords: &Coords, value: char) {
-    self.grid.set(coords, value)
+  fn shift_boxes(&mut self, boxes: &[usize], direction: &UnitVector) {
+    for &i in boxes {
+      for c in self.boxes[i].cells().collect_vec() {
+        self.occupancy.remove(&c);
+      }
+    }
+
+    for &i in boxes {
+      self.boxes[i].position = (self.boxes[i].position + direction).to_coords().unwrap();
+    }
+
+    self.occupy(boxes);
   }
 
-  fn try_push(&mut self, direction: &UnitVector) {
-    if direction.1 == 0 {
-      if let Some(free_space) = self.find_free_space(direction) {
-        for w in self.grid.get_coords_between(&free_space, &self.current_position).windows(2) {
-          self.set(&w[0], self.get(&w[1]));
-        }
-        self.make_move(direction)
+  fn occupy(&mut self, boxes: &[usize]) {
+    for &i in boxes {
+      for c in self.boxes[i].cells().collect_vec() {
+        self.occupancy.insert(c, i);
       }
     }
-    else {
-      let bigbox = self.get_big_box_coordinates((self.current_position + direction).to_coords().unwrap());
-      if self.can_bigbox_be_pushed(bigbox, direction) {
-        self.push_bigbox(bigbox, direction);
-        self.make_move(direction);
+  }
+
+  fn render(&self) -> String {
+    let mut picture = self.floor.clone();
+
+    for b in self.boxes.iter() {
+      for c in b.cells() {
+        let symbol = if b.width == 1 {
+          'O'
+        } else if c.0 == b.position.0 {
+          '['
+        } else if c.0 == b.position.0 + b.width - 1 {
+          ']'
+        } else {
+          '='
+        };
+        picture.set(&c, symbol);
       }
     }
+    picture.set(&self.robot, '@');
+
+    picture
+      .grid
+      .iter()
+      .map(|row| row.iter().collect::<String>())
+      .join("\n")
+  }
+
+  fn sum_boxes_gps_coordinates(&self) -> usize {
+    self.boxes.iter().map(|b| b.gps_coordinate()).sum()
   }
 }
 
 type UnitVector = ICoords;
-type BigBox = (Coords, Coords);
 
 #[cfg(test)]
 mod tests {
   use crate::{
-    day15::{sum_boxes_gps_coordinates, sum_scaled_up_boxes_gps_coordinates},
-    read,
+    day15::{
+      sum_boxes_gps_coordinates, sum_boxes_gps_coordinates_with_scale, sum_scaled_up_boxes_gps_coordinates, Scale,
+      WarehouseSimulator,
+    },
+    inputs::open,
+    Coords,
   };
 
   #[test]
   fn smaller_sample_part1_input() {
     assert_eq!(
-      sum_boxes_gps_coordinates(&mut read("./src/day15/smaller.sample.input")),
+      sum_boxes_gps_coordinates(&mut open(15, "smaller.sample")),
       2028
     )
   }
@@ -325,7 +421,7 @@ mod tests {
   #[test]
   fn bigger_sample_part1_input() {
     assert_eq!(
-      sum_boxes_gps_coordinates(&mut read("./src/day15/larger.sample.input")),
+      sum_boxes_gps_coordinates(&mut open(15, "larger.sample")),
       10092
     )
   }
@@ -333,7 +429,7 @@ mod tests {
   #[test]
   fn my_part1_input() {
     assert_eq!(
-      sum_boxes_gps_coordinates(&mut read("./src/day15/my.input")),
+      sum_boxes_gps_coordinates(&mut open(15, "my")),
       1457740
     )
   }
@@ -341,7 +437,7 @@ mod tests {
   #[test]
   fn smaller_sample_part2_input() {
     assert_eq!(
-      sum_scaled_up_boxes_gps_coordinates(&mut read("./src/day15/smaller.part2.sample.input")),
+      sum_scaled_up_boxes_gps_coordinates(&mut open(15, "smaller.part2.sample")),
       105 + 2 * 100 + 7 + 3 * 100 + 6
     )
   }
@@ -349,7 +445,7 @@ mod tests {
   #[test]
   fn larger_sample_part2_input() {
     assert_eq!(
-      sum_scaled_up_boxes_gps_coordinates(&mut read("./src/day15/larger.sample.input")),
+      sum_scaled_up_boxes_gps_coordinates(&mut open(15, "larger.sample")),
       9021
     )
   }
@@ -357,8 +453,99 @@ mod tests {
   #[test]
   fn my_part2_input() {
     assert_eq!(
-      sum_scaled_up_boxes_gps_coordinates(&mut read("./src/day15/my.input")),
+      sum_scaled_up_boxes_gps_coordinates(&mut open(15, "my")),
       1467145
     )
   }
+
+  #[test]
+  fn smaller_sample_simulator_history() {
+    let mut simulator = WarehouseSimulator::new(&mut open(15, "smaller.sample"));
+    simulator.jump_to(5);
+
+    let last = simulator.history().last().unwrap();
+    assert_eq!(last.direction, '>');
+    assert_eq!(last.robot_before, Coords::new(3, 1));
+    assert_eq!(last.robot_after, Coords::new(4, 1));
+    assert_eq!(last.pushed_boxes, vec![Coords::new(4, 1), Coords::new(5, 1)]);
+    assert!(simulator.history()[0].pushed_boxes.is_empty());
+  }
+
+  #[test]
+  fn larger_sample_simulator_undo_redo() {
+    let mut simulator = WarehouseSimulator::new(&mut open(15, "larger.sample"));
+    let initial = simulator.render();
+
+    simulator.jump_to(simulator.len());
+    assert_eq!(simulator.sum_boxes_gps_coordinates(), 10092);
+    let last = simulator.render();
+
+    simulator.jump_to(100);
+    let middle = simulator.render();
+    assert_eq!(simulator.undo().map(|r| r.direction), Some('<'));
+    assert_eq!(simulator.step(), 99);
+    assert_eq!(simulator.redo().map(|r| r.direction), Some('<'));
+    assert_eq!(simulator.render(), middle);
+
+    simulator.jump_to(0);
+    assert_eq!(simulator.render(), initial);
+    simulator.jump_to(simulator.len());
+    assert_eq!(simulator.render(), last);
+    assert_eq!(simulator.redo(), None);
+  }
+
+  #[test]
+  fn larger_sample_scaled_up_simulator_replay() {
+    let mut simulator = WarehouseSimulator::scaled_up(&mut open(15, "larger.sample"));
+    let mut frames: Vec<String> = Vec::new();
+    simulator.replay(|_, frame| frames.push(frame.to_string()));
+
+    assert_eq!(frames.len(), simulator.len() + 1);
+    assert_eq!(simulator.sum_boxes_gps_coordinates(), 9021);
+    assert!(frames[0].starts_with("####################\n##....[]....[]..[]##"));
+
+    simulator.jump_to(0);
+    assert_eq!(simulator.render(), frames[0]);
+  }
+
+  #[test]
+  fn tall_boxes_are_pushed_as_a_whole() {
+    let mut simulator = WarehouseSimulator::with_scale(&mut open(15, "scaled.sample"), Scale::new(2, 2));
+    simulator.jump_to(simulator.len());
+
+    assert_eq!(simulator.robot(), Coords::new(4, 4));
+    assert_eq!(simulator.boxes()[0].height(), 2);
+    assert_eq!(simulator.sum_boxes_gps_coordinates(), 204);
+    assert!(simulator.history()[2].pushed_boxes.is_empty());
+  }
+
+  #[test]
+  fn wide_boxes_are_rendered_with_filling() {
+    let mut simulator = WarehouseSimulator::with_scale(&mut open(15, "scaled.sample"), Scale::new(3, 1));
+    assert_eq!(simulator.render().lines().nth(2), Some("###...[=]......###"));
+
+    simulator.jump_to(simulator.len());
+    assert_eq!(simulator.render().lines().nth(1), Some("###...[=]......###"));
+    assert_eq!(
+      sum_boxes_gps_coordinates_with_scale(&mut open(15, "scaled.sample"), Scale::new(3, 1)),
+      106
+    );
+  }
+
+  #[test]
+  fn larger_sample_part2_input_drawn_at_double_width() {
+    use std::io::Read;
+
+    let mut simulator = WarehouseSimulator::scaled_up(&mut open(15, "larger.sample"));
+    let drawn = simulator.render();
+    let mut moves = String::new();
+    open(15, "larger.sample").read_to_string(&mut moves).unwrap();
+    let moves = moves.split("\n\n").nth(1).unwrap();
+
+    simulator.jump_to(simulator.len());
+    assert_eq!(
+      sum_boxes_gps_coordinates(&mut format!("{}\n\n{}", drawn, moves).as_bytes()),
+      simulator.sum_boxes_gps_coordinates()
+    );
+  }
 }
@@ -1,21 +1,123 @@
-use std::{collections::{BinaryHeap, HashMap, HashSet}, io::BufRead};
+use std::{collections::{BinaryHeap, HashMap, HashSet, VecDeque}, io::BufRead};
 
 use itertools::Itertools;
 
 use crate::{read_input, CartesianGrid, Coords, GridCoords, ICoords};
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Heading {
+  North,
+  East,
+  South,
+  West,
+}
+
+impl Heading {
+  fn vector(&self) -> ICoords {
+    match self {
+      Heading::North => ICoords(0, -1),
+      Heading::East => ICoords(1, 0),
+      Heading::South => ICoords(0, 1),
+      Heading::West => ICoords(-1, 0),
+    }
+  }
+
+  fn turn(&self, turn: &Turn) -> Heading {
+    let headings = [Heading::North, Heading::East, Heading::South, Heading::West];
+    let index = headings.iter().position(|h| h == self).unwrap();
+    headings[(index + turn.quarters() as usize) % 4]
+  }
+
+  fn arrow(&self) -> char {
+    match self {
+      Heading::North => '^',
+      Heading::East => '>',
+      Heading::South => 'v',
+      Heading::West => '<',
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Turn {
+  Right,
+  Around,
+  Left,
+}
+
+impl Turn {
+  fn quarters(&self) -> u32 {
+    match self {
+      Turn::Right => 1,
+      Turn::Around => 2,
+      Turn::Left => 3,
+    }
+  }
+
+  fn cost_multiplier(&self) -> u32 {
+    match self {
+      Turn::Around => 2,
+      _ => 1,
+    }
+  }
+}
+
+#[derive(Clone, Debug)]
+pub struct MazeRules {
+  step_cost: u32,
+  turn_cost: u32,
+  turns: Vec<Turn>,
+  start_heading: Heading,
+}
+
+impl Default for MazeRules {
+  fn default() -> Self {
+    MazeRules {
+      step_cost: 1,
+      turn_cost: 1000,
+      turns: vec![Turn::Right, Turn::Around, Turn::Left],
+      start_heading: Heading::East,
+    }
+  }
+}
+
+impl MazeRules {
+  pub fn with_costs(self, step_cost: u32, turn_cost: u32) -> Self {
+    MazeRules { step_cost, turn_cost, ..self }
+  }
+
+  pub fn with_turns(self, turns: &[Turn]) -> Self {
+    MazeRules { turns: turns.to_vec(), ..self }
+  }
+
+  pub fn with_start_heading(self, start_heading: Heading) -> Self {
+    MazeRules { start_heading, ..self }
+  }
+
+  fn moves(&self, heading: Heading) -> Vec<(Heading, u32)> {
+    std::iter::once((heading, self.step_cost))
+      .chain(
+        self
+          .turns
+          .iter()
+          .map(|t| (heading.turn(t), self.step_cost + self.turn_cost * t.cost_multiplier())),
+      )
+      .collect_vec()
+  }
+}
+
 #[derive(PartialEq, Eq, Hash, Copy, Clone)]
 struct State {
   position: Coords,
-  direction: ICoords,
+  heading: Heading,
   cost: u32
 }
 
 impl State {
-  fn new(position: Coords, direction: ICoords, cost: u32) -> State {
+  fn new(position: Coords, heading: Heading, cost: u32) -> State {
     State {
       position,
-      direction,
+      heading,
       cost
     }
   }
@@ -33,112 +135,153 @@ impl Ord for State {
   }
 }
 
+type Node = (Coords, Heading);
+
+struct MazeVisit {
+  start: Coords,
+  end: Coords,
+  dist: HashMap<Node, u32>,
+  predecessors: HashMap<Node, HashSet<Node>>,
+  min: Option<u32>,
+}
+
+impl MazeVisit {
+  fn best_ends(&self) -> Vec<Node> {
+    self.dist.iter()
+      .filter(|((pos, _), cost)| *pos == self.end && Some(**cost) == self.min)
+      .map(|(node, _)| *node)
+      .sorted_by_key(|(_, heading)| *heading)
+      .collect_vec()
+  }
+}
+
 pub fn lowest_score_path(input: &mut dyn BufRead) -> u32 {
-  visit_maze(CartesianGrid::from(read_input(input))).3
+  lowest_score_path_with_rules(input, &MazeRules::default()).unwrap()
+}
+
+pub fn lowest_score_path_with_rules(input: &mut dyn BufRead, rules: &MazeRules) -> Option<u32> {
+  visit_maze(CartesianGrid::from(read_input(input)), rules).min
 }
 
 pub fn best_path_fields(input: &mut dyn BufRead) -> usize {
-  let (end, dist, predecessors, min) = visit_maze(CartesianGrid::from(read_input(input)));
-  let ends: Vec<(Coords, ICoords)> = (*dist.iter()
-    .filter(|((pos, _), _)| *pos == end)
-    .filter(|(_, cost)| **cost == min)
-    .map(|(c, _)| *c)
-    .collect_vec()).to_vec();
-  
-  let mut visited: HashSet<Coords> = HashSet::new();
+  best_path_fields_with_rules(input, &MazeRules::default())
+}
+
+pub fn best_path_fields_with_rules(input: &mut dyn BufRead, rules: &MazeRules) -> usize {
+  let maze = visit_maze(CartesianGrid::from(read_input(input)), rules);
   
-  for e in ends {
-    let mut queue: Vec<(Coords, ICoords)> = Vec::new();
-    queue.push(e);
+  let mut visited: HashSet<Node> = HashSet::new();
+  let mut queue: Vec<Node> = maze.best_ends();
+
+  while let Some(node) = queue.pop() {
+    if visited.insert(node)
+      && let Some(preds) = maze.predecessors.get(&node)
+    {
+      queue.extend(preds.iter().copied());
+    }
+  }
+
+  visited.iter().map(|(position, _)| position).unique().count()
+}
+
+pub fn render_best_path(input: &mut dyn BufRead, rules: &MazeRules) -> Option<String> {
+  let mut grid = CartesianGrid::from(read_input(input));
+  let maze = visit_maze(grid.clone(), rules);
+
+  let start: Node = (maze.start, rules.start_heading);
+  let end = *maze.best_ends().first()?;
 
-    while let Some(node) = queue.pop() {
-      visited.insert(node.0);
+  let mut next_on_path: HashMap<Node, Node> = HashMap::new();
+  let mut visited: HashSet<Node> = HashSet::from([end]);
+  let mut queue: VecDeque<Node> = VecDeque::from([end]);
 
-      if let Some(preds) = predecessors.get(&node) {
-        for p in preds.clone() {
-          queue.push(p);
-        }
+  while let Some(node) = queue.pop_front() {
+    if node == start {
+      break;
+    }
+
+    for prev in maze.predecessors.get(&node).into_iter().flatten().sorted_by_key(|p| (p.0.1, p.0.0, p.1)) {
+      if visited.insert(*prev) {
+        next_on_path.insert(*prev, node);
+        queue.push_back(*prev);
       }
     }
   }
 
-  visited.len()
+  let mut node = start;
+  while let Some(next) = next_on_path.get(&node) {
+    if node.0 != maze.start && node.0 != maze.end {
+      grid.set(&node.0, next.1.arrow());
+    }
+    node = *next;
+  }
+
+  Some(grid.grid.iter().map(|row| row.iter().collect::<String>()).join("\n"))
 }
 
-fn visit_maze(maze: CartesianGrid<char>) -> (Coords, HashMap<(Coords, ICoords), u32>, HashMap<(Coords, ICoords), HashSet<(Coords, ICoords)>>, u32) {
+fn visit_maze(maze: CartesianGrid<char>, rules: &MazeRules) -> MazeVisit {
   let start = maze.find_one_coords('S').unwrap();
   let end = maze.find_one_coords('E').unwrap();
 
-  let mut dist: HashMap<(Coords, ICoords), u32> = HashMap::new();
-  let mut prev: HashMap<(Coords, ICoords), HashSet<(Coords, ICoords)>> = HashMap::new();
+  let mut dist: HashMap<Node, u32> = HashMap::new();
+  let mut prev: HashMap<Node, HashSet<Node>> = HashMap::new();
   let mut queue: BinaryHeap<State> = BinaryHeap::new();
-  let mut visited: HashSet<(Coords, ICoords)> = HashSet::new();
+  let mut visited: HashSet<Node> = HashSet::new();
   
-  queue.push(State::new(start, ICoords(1, 0), 0));
+  dist.insert((start, rules.start_heading), 0);
+  queue.push(State::new(start, rules.start_heading, 0));
 
   while let Some(curr) = queue.pop() {
-    visited.insert((curr.position, curr.direction));
+    if !visited.insert((curr.position, curr.heading)) {
+      continue;
+    }
 
-    for (next, next_dir) in maze.neighbors(&curr.position).iter()
-      .map(|n| (*n, maze.dir_after(&curr.position, n)))
-      .filter(|n| !visited.contains(n)) 
-    {
-      let alt = curr.cost + maze.cost(&curr.position, &curr.direction, &next);
-      let old = dist.get(&(next, next_dir));
+    for (heading, cost) in rules.moves(curr.heading) {
+      let Some(next) = maze.step(&curr.position, &heading) else {
+        continue;
+      };
 
-      if old.is_none() || alt < *old.unwrap() {
-        prev.insert((next, next_dir), HashSet::new());
-      }
+      let alt = curr.cost + cost;
+      let old = dist.get(&(next, heading)).copied();
 
-      prev.entry((next, next_dir))
-        .and_modify(|s| { s.insert((curr.position, curr.direction)); })
-        .or_insert(HashSet::new());
-      dist.insert((next, next_dir), alt);
-      queue.push(State::new(next, maze.dir_after(&curr.position, &next), alt));
+      if old.is_none_or(|old| alt < old) {
+        dist.insert((next, heading), alt);
+        prev.insert((next, heading), HashSet::from([(curr.position, curr.heading)]));
+        queue.push(State::new(next, heading, alt));
+      } else if old == Some(alt) {
+        prev.entry((next, heading)).or_default().insert((curr.position, curr.heading));
+      }
     }
   }
 
-  let min = *dist.iter()
+  let min = dist.iter()
     .filter(|((pos, _), _)| *pos == end)
-    .map(|(_, cost)| cost)
-    .min()
-    .unwrap();
+    .map(|(_, cost)| *cost)
+    .min();
 
-  (end, dist, prev, min)
+  MazeVisit { start, end, dist, predecessors: prev, min }
 }
 
 impl CartesianGrid<char> {
-  fn neighbors(&self, coords: &Coords, ) -> Vec<Coords> {
-    vec![
-      coords.sub_y(1),
-      coords.add_x(1),
-      coords.add_y(1),
-      coords.sub_x(1)
-    ]
-    .iter()
-    .filter(|c| c.in_grid(self))
-    .flat_map(|c| c.to_coords())
-    .filter(|c| *self.get(c) != '#')
-    .collect_vec()
-  }
-
-  fn cost(&self, from: &Coords, direction: &ICoords, to: &Coords) -> u32 {
-    if self.dir_after(from, to) == *direction {
-      1
-    }
-    else {
-      1001
-    }
-  }
+  fn step(&self, coords: &Coords, heading: &Heading) -> Option<Coords> {
+    let next = *coords + heading.vector();
 
-  fn dir_after(&self, from: &Coords, to: &Coords) -> ICoords {
-    to - from
+    Some(next)
+      .filter(|c| c.in_grid(self))
+      .and_then(|c| c.to_coords())
+      .filter(|c| *self.get(c) != '#')
   }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{day16::{lowest_score_path, best_path_fields}, read};
+    use crate::{
+      day16::{
+        best_path_fields, best_path_fields_with_rules, lowest_score_path, lowest_score_path_with_rules,
+        render_best_path, Heading, MazeRules, Turn,
+      },
+      read,
+    };
 
   #[test]
   fn first_sample_part1_input() {
@@ -169,4 +312,74 @@ mod tests {
   fn my_part2_input() {
     assert_eq!(best_path_fields(&mut read("./src/day16/my.input")), 593)
   }
-}
\ No newline at end of file
+
+  #[test]
+  fn first_sample_starting_north() {
+    assert_eq!(
+      lowest_score_path_with_rules(
+        &mut read("./src/day16/first.sample.input"),
+        &MazeRules::default().with_start_heading(Heading::North)
+      ),
+      Some(6036)
+    )
+  }
+
+  #[test]
+  fn first_sample_with_free_turns() {
+    let rules = MazeRules::default().with_costs(1, 0);
+    assert_eq!(lowest_score_path_with_rules(&mut read("./src/day16/first.sample.input"), &rules), Some(28));
+    assert_eq!(best_path_fields_with_rules(&mut read("./src/day16/first.sample.input"), &rules), 37);
+  }
+
+  #[test]
+  fn first_sample_without_turning_around() {
+    let rules = MazeRules::default()
+      .with_start_heading(Heading::West)
+      .with_turns(&[Turn::Left, Turn::Right]);
+    assert_eq!(lowest_score_path_with_rules(&mut read("./src/day16/first.sample.input"), &rules), Some(7036));
+  }
+
+  #[test]
+  fn first_sample_without_turns_is_unsolvable() {
+    let rules = MazeRules::default().with_turns(&[]);
+    assert_eq!(lowest_score_path_with_rules(&mut read("./src/day16/first.sample.input"), &rules), None);
+    assert_eq!(render_best_path(&mut read("./src/day16/first.sample.input"), &rules), None);
+  }
+
+  #[test]
+  fn first_sample_with_free_moves() {
+    let rules = MazeRules::default().with_costs(0, 0);
+    let rendered = render_best_path(&mut read("./src/day16/first.sample.input"), &rules).unwrap();
+
+    assert_eq!(lowest_score_path_with_rules(&mut read("./src/day16/first.sample.input"), &rules), Some(0));
+    assert!(rendered.contains('S') && rendered.contains('E'));
+    assert!(rendered.chars().any(|c| "^>v<".contains(c)));
+  }
+
+  #[test]
+  fn first_sample_best_path_rendering() {
+    assert_eq!(
+      render_best_path(&mut read("./src/day16/first.sample.input"), &MazeRules::default()),
+      Some(
+        [
+          "###############",
+          "#.......#....E#",
+          "#.#.###.#.###^#",
+          "#.....#.#...#^#",
+          "#.###.#####.#^#",
+          "#.#.#.......#^#",
+          "#.#.#####.###^#",
+          "#....>>>>>>v#^#",
+          "###.#^#####v#^#",
+          "#...#^....#v#^#",
+          "#.#.#^###.#v#^#",
+          "#>>>>^#...#v#^#",
+          "#^###.#.#.#v#^#",
+          "#S..#.....#>>^#",
+          "###############",
+        ]
+        .join("\n")
+      )
+    )
+  }
+}
@@ -0,0 +1,56 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use day01::{
+  day18::{
+    find_first_blocking_byte_by_replaying, find_first_blocking_byte_with_binary_search,
+    find_first_blocking_byte_with_union_find,
+  },
+  Coords,
+};
+
+fn generate_bytes(size: usize) -> Vec<Coords> {
+  let mut cells: Vec<Coords> = (0..size)
+    .flat_map(|y| (0..size).map(move |x| Coords::new(x, y)))
+    .filter(|c| *c != Coords::new(0, 0) && *c != Coords::new(size - 1, size - 1))
+    .collect();
+
+  let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+  for i in (1..cells.len()).rev() {
+    seed ^= seed << 13;
+    seed ^= seed >> 7;
+    seed ^= seed << 17;
+    cells.swap(i, (seed % (i as u64 + 1)) as usize);
+  }
+
+  cells
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+  let mut group = c.benchmark_group("first_blocking_byte");
+  group.sample_size(10);
+
+  for size in [71, 101, 301, 1001] {
+    let bytes = generate_bytes(size);
+
+    group.bench_with_input(BenchmarkId::new("union_find", size), &bytes, |b, bytes| {
+      b.iter(|| find_first_blocking_byte_with_union_find(bytes, (size, size)))
+    });
+    if size <= 301 {
+      group.bench_with_input(BenchmarkId::new("binary_search", size), &bytes, |b, bytes| {
+        b.iter(|| find_first_blocking_byte_with_binary_search(bytes, (size, size)))
+      });
+    }
+
+    // Replaying runs one BFS per fallen byte: ~4s per run at 71 and ~77s at 141, so 101 is the largest size
+    // that still finishes the minimum of 10 samples in a few minutes.
+    if size <= 101 {
+      group.bench_with_input(BenchmarkId::new("replaying", size), &bytes, |b, bytes| {
+        b.iter(|| find_first_blocking_byte_by_replaying(bytes, (size, size)))
+      });
+    }
+  }
+
+  group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
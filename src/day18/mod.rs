@@ -2,7 +2,7 @@ use std::{collections::{BinaryHeap, HashMap, HashSet}, io::BufRead};
 
 use itertools::Itertools;
 
-use crate::{read_input, CartesianGrid, Coords, GridCoords};
+use crate::{read_input, CartesianGrid, Coords, GridCoords, ICoords};
 
 pub fn minimum_number_of_steps_needed_to_reach_the_exit(input: &mut dyn BufRead, size: (usize, usize), n: usize) -> usize {
   let bytes = parse_input(read_input(input));
@@ -18,19 +18,192 @@ pub fn minimum_number_of_steps_needed_to_reach_the_exit(input: &mut dyn BufRead,
 
 pub fn find_first_byte_that_will_prevent_the_exit(input: &mut dyn BufRead, size: (usize, usize)) -> String {
   let bytes = parse_input(read_input(input));
-  
-  for n in 1..=bytes.len() {
-    let mut space = CartesianGrid::empty(size);
-    for b in bytes.iter().take(n) {
-      space.set(&b, '#');
+
+  find_first_blocking_byte_with_union_find(&bytes, size)
+    .map(|b| format!("{},{}", b.coords.0, b.coords.1))
+    .expect("No byte will prevent the exit")
+}
+
+pub fn shortest_escape_time_while_bytes_fall(input: &mut dyn BufRead, size: (usize, usize)) -> Option<usize> {
+  let bytes = parse_input(read_input(input));
+  escape_while_bytes_fall(&bytes, size)
+}
+
+pub fn escape_while_bytes_fall(bytes: &[Coords], size: (usize, usize)) -> Option<usize> {
+  let (width, height) = size;
+  let mut fall_times = vec![usize::MAX; width * height];
+  for (time, byte) in bytes.iter().enumerate().rev() {
+    fall_times[byte.1 * width + byte.0] = time;
+  }
+
+  let is_free = |c: &Coords, time: usize| fall_times[c.1 * width + c.0] > time;
+  let start = Coords(0, 0);
+  let end = Coords(width - 1, height - 1);
+  let time_limit = bytes.len() + width * height;
+
+  let mut positions: HashSet<Coords> = HashSet::new();
+  if is_free(&start, 0) {
+    positions.insert(start);
+  }
+
+  for time in 0..=time_limit {
+    if positions.contains(&end) {
+      return Some(time);
     }
+    if positions.is_empty() {
+      return None;
+    }
+
+    positions = positions
+      .iter()
+      .flat_map(|p| space_neighbors4(p, size))
+      .filter(|c| is_free(c, time + 1))
+      .collect();
+  }
+
+  None
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BlockingByte {
+  pub index: usize,
+  pub coords: Coords,
+}
+
+pub fn find_first_blocking_byte_by_replaying(bytes: &[Coords], size: (usize, usize)) -> Option<BlockingByte> {
+  (1..=bytes.len())
+    .find(|&n| !is_exit_reachable(bytes, size, n))
+    .map(|n| BlockingByte {
+      index: n - 1,
+      coords: bytes[n - 1],
+    })
+}
+
+pub fn find_first_blocking_byte_with_binary_search(bytes: &[Coords], size: (usize, usize)) -> Option<BlockingByte> {
+  let (mut lo, mut hi) = (0, bytes.len());
+
+  if is_exit_reachable(bytes, size, hi) {
+    return None;
+  }
 
-    if find_shortest_path_length(&space, Coords(0, 0), Coords(size.0 - 1, size.1 - 1)).is_none() {
-      return bytes.get(n - 1).map(|c| format!("{},{}", c.0, c.1)).unwrap();
+  while hi - lo > 1 {
+    let mid = (lo + hi) / 2;
+    if is_exit_reachable(bytes, size, mid) {
+      lo = mid;
+    } else {
+      hi = mid;
+    }
+  }
+
+  Some(BlockingByte {
+    index: hi - 1,
+    coords: bytes[hi - 1],
+  })
+}
+
+pub fn find_first_blocking_byte_with_union_find(bytes: &[Coords], size: (usize, usize)) -> Option<BlockingByte> {
+  let (width, height) = size;
+  let top_right_border = width * height;
+  let bottom_left_border = top_right_border + 1;
+  let mut walls = DisjointSet::new(width * height + 2);
+  let mut blocked = vec![false; width * height];
+
+  for (index, byte) in bytes.iter().enumerate() {
+    let cell = byte.1 * width + byte.0;
+    blocked[cell] = true;
+
+    if byte.1 == 0 || byte.0 == width - 1 {
+      walls.union(cell, top_right_border);
+    }
+    if byte.0 == 0 || byte.1 == height - 1 {
+      walls.union(cell, bottom_left_border);
+    }
+
+    for neighbor in space_neighbors8(byte, size) {
+      if blocked[neighbor.1 * width + neighbor.0] {
+        walls.union(cell, neighbor.1 * width + neighbor.0);
+      }
+    }
+
+    if walls.find(top_right_border) == walls.find(bottom_left_border) {
+      return Some(BlockingByte { index, coords: *byte });
+    }
+  }
+
+  None
+}
+
+fn is_exit_reachable(bytes: &[Coords], size: (usize, usize), n: usize) -> bool {
+  let mut space = CartesianGrid::empty(size);
+  for b in bytes.iter().take(n) {
+    space.set(b, '#');
+  }
+
+  *space.get(&Coords(0, 0)) != '#'
+    && find_shortest_path_length(&space, Coords(0, 0), Coords(size.0 - 1, size.1 - 1)).is_some()
+}
+
+fn space_neighbors4(coords: &Coords, size: (usize, usize)) -> Vec<Coords> {
+  [ICoords(0, -1), ICoords(1, 0), ICoords(0, 1), ICoords(-1, 0)]
+    .iter()
+    .flat_map(|d| (coords + d).to_coords())
+    .filter(|c| c.0 < size.0 && c.1 < size.1)
+    .collect()
+}
+
+fn space_neighbors8(coords: &Coords, size: (usize, usize)) -> Vec<Coords> {
+  (-1..=1)
+    .flat_map(|dy| (-1..=1).map(move |dx| ICoords(dx, dy)))
+    .filter(|d| *d != ICoords(0, 0))
+    .flat_map(|d| (coords + d).to_coords())
+    .filter(|c| c.0 < size.0 && c.1 < size.1)
+    .collect()
+}
+
+struct DisjointSet {
+  parents: Vec<usize>,
+  ranks: Vec<usize>,
+}
+
+impl DisjointSet {
+  fn new(size: usize) -> DisjointSet {
+    DisjointSet {
+      parents: (0..size).collect(),
+      ranks: vec![0; size],
+    }
+  }
+
+  fn find(&mut self, x: usize) -> usize {
+    let mut root = x;
+    while self.parents[root] != root {
+      root = self.parents[root];
+    }
+
+    let mut current = x;
+    while self.parents[current] != root {
+      let next = self.parents[current];
+      self.parents[current] = root;
+      current = next;
+    }
+
+    root
+  }
+
+  fn union(&mut self, a: usize, b: usize) {
+    let (a, b) = (self.find(a), self.find(b));
+    if a == b {
+      return;
+    }
+
+    match self.ranks[a].cmp(&self.ranks[b]) {
+      std::cmp::Ordering::Less => self.parents[a] = b,
+      std::cmp::Ordering::Greater => self.parents[b] = a,
+      std::cmp::Ordering::Equal => {
+        self.parents[b] = a;
+        self.ranks[a] += 1;
+      }
     }
   }
-  
-  panic!("No byte will prevent the exit");
 }
 
 fn find_shortest_path_length(space: &CartesianGrid<char>, start: Coords, end: Coords) -> Option<usize> {
@@ -62,7 +235,7 @@ fn find_shortest_path_length(space: &CartesianGrid<char>, start: Coords, end: Co
   None
 }
 
-fn parse_input(lines: Vec<String>) -> Vec<Coords> {
+pub fn parse_input(lines: Vec<String>) -> Vec<Coords> {
   fn parse_coord(line: &str) -> Coords {
     line.split_once(',').map(|(x, y)| Coords(x.parse().unwrap(), y.parse().unwrap())).unwrap()
   }
@@ -114,7 +287,15 @@ impl Ord for State {
 
 #[cfg(test)]
 mod tests {
-  use crate::{day18::{find_first_byte_that_will_prevent_the_exit, minimum_number_of_steps_needed_to_reach_the_exit}, read};
+  use crate::{
+    day18::{
+      find_first_blocking_byte_by_replaying, find_first_blocking_byte_with_binary_search,
+      find_first_blocking_byte_with_union_find, find_first_byte_that_will_prevent_the_exit,
+      escape_while_bytes_fall, minimum_number_of_steps_needed_to_reach_the_exit, parse_input,
+      shortest_escape_time_while_bytes_fall, BlockingByte,
+    },
+    read, read_input, Coords,
+  };
 
   #[test]
   fn sample_part1_input() {
@@ -135,4 +316,71 @@ mod tests {
   fn my_part2_input() {
     assert_eq!(find_first_byte_that_will_prevent_the_exit(&mut read("./src/day18/my.input"), (71, 71)), "30,12")
   }
-}
\ No newline at end of file
+
+  #[test]
+  fn sample_blocking_byte_variants() {
+    let bytes = parse_input(read_input(&mut read("./src/day18/sample.input")));
+    let expected = Some(BlockingByte {
+      index: 20,
+      coords: Coords::new(6, 1),
+    });
+
+    assert_eq!(find_first_blocking_byte_by_replaying(&bytes, (7, 7)), expected);
+    assert_eq!(find_first_blocking_byte_with_binary_search(&bytes, (7, 7)), expected);
+    assert_eq!(find_first_blocking_byte_with_union_find(&bytes, (7, 7)), expected);
+  }
+
+  #[test]
+  fn my_blocking_byte_variants() {
+    let bytes = parse_input(read_input(&mut read("./src/day18/my.input")));
+    let expected = find_first_blocking_byte_with_binary_search(&bytes, (71, 71));
+
+    assert_eq!(expected.map(|b| b.coords), Some(Coords::new(30, 12)));
+    assert_eq!(find_first_blocking_byte_with_union_find(&bytes, (71, 71)), expected);
+  }
+
+  #[test]
+  fn no_blocking_byte() {
+    let bytes = vec![Coords::new(1, 1), Coords::new(0, 2)];
+
+    assert_eq!(find_first_blocking_byte_with_binary_search(&bytes, (3, 3)), None);
+    assert_eq!(find_first_blocking_byte_with_union_find(&bytes, (3, 3)), None);
+  }
+
+  #[test]
+  fn byte_falling_on_exit_blocks_it() {
+    let bytes = vec![Coords::new(1, 1), Coords::new(2, 2)];
+    let expected = Some(BlockingByte {
+      index: 1,
+      coords: Coords::new(2, 2),
+    });
+
+    assert_eq!(find_first_blocking_byte_with_binary_search(&bytes, (3, 3)), expected);
+    assert_eq!(find_first_blocking_byte_with_union_find(&bytes, (3, 3)), expected);
+  }
+
+  #[test]
+  fn sample_escape_while_bytes_fall() {
+    assert_eq!(shortest_escape_time_while_bytes_fall(&mut read("./src/day18/sample.input"), (7, 7)), Some(12))
+  }
+
+  #[test]
+  fn my_escape_while_bytes_fall() {
+    assert_eq!(shortest_escape_time_while_bytes_fall(&mut read("./src/day18/my.input"), (71, 71)), Some(140))
+  }
+
+  #[test]
+  fn escape_avoids_bytes_falling_on_the_way() {
+    let bytes = vec![Coords::new(1, 1), Coords::new(0, 0), Coords::new(2, 0)];
+    assert_eq!(escape_while_bytes_fall(&bytes, (3, 3)), Some(4));
+
+    let bytes = vec![Coords::new(1, 1), Coords::new(0, 0), Coords::new(2, 0), Coords::new(1, 2)];
+    assert_eq!(escape_while_bytes_fall(&bytes, (3, 3)), None);
+  }
+
+  #[test]
+  fn no_escape_when_trapped_at_start() {
+    let bytes = vec![Coords::new(1, 0), Coords::new(0, 1)];
+    assert_eq!(escape_while_bytes_fall(&bytes, (3, 3)), None);
+  }
+}
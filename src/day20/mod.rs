@@ -1,143 +1,217 @@
-use std::{collections::{BinaryHeap, HashMap, HashSet}, io::BufRead};
+use std::{
+  cmp::Reverse,
+  collections::{BTreeMap, VecDeque},
+  io::BufRead,
+};
 
-use itertools::Itertools;
-
-use crate::{read_input, CartesianGrid, Coords, GridCoords, ICoords};
+use crate::{read_input, CartesianGrid, Coords, ICoords};
 
 pub fn how_many_m_lasting_cheats_would_save_at_least_n_picoseconds(input: &mut dyn BufRead, m: usize, n: usize) -> usize {
-  let map = CartesianGrid::from(read_input(input));
-  let start = map.find_one_coords('S').unwrap();
-  let end = map.find_one_coords('E').unwrap();
-  let possible_cheats = find_possible_cheats(&map, &start, m);
-  let dist = map.find_best_path(&start, &end);
-
-  possible_cheats.iter()
-    .flat_map(|(pos, cheats)| cheats.iter()
-      .filter(|cheat| *dist.get(&cheat).unwrap() as isize - *dist.get(pos).unwrap() as isize - manhattan_distance(pos, cheat) as isize + 1 >= n as isize)
-    )
-    .count()
+  cheat_savings_histogram(input, m)
+    .range(n..)
+    .map(|(_, count)| count)
+    .sum()
 }
 
-fn manhattan_distance(a: &Coords, b: &Coords) -> usize {
-  let diff = a - b;
-  diff.0.abs() as usize + diff.1.abs() as usize
-}
+pub fn cheat_savings_histogram(input: &mut dyn BufRead, m: usize) -> BTreeMap<usize, usize> {
+  let map = CartesianGrid::from(read_input(input));
+  let starts = map.find_all_coords('S');
+  let ends = map.find_all_coords('E');
 
-fn find_possible_cheats(map: &CartesianGrid<char>, start: &Coords, distance: usize) -> HashMap<Coords, HashSet<Coords>> {
-  let mut possible_cheats: HashMap<Coords, HashSet<Coords>> = HashMap::new();
-  let mut visited: HashSet<Coords> = HashSet::new();
-  let mut queue: Vec<Coords> = vec![*start];
-  
-  while let Some(current) = queue.pop() {
-    visited.insert(current);
-    possible_cheats.insert(current, map.find_possible_cheats(current, distance));
-
-    for neighbor in map.neighbors(&current) {
-      if !visited.contains(&neighbor) {
-        queue.push(neighbor);
-      }
+  let mut histogram: BTreeMap<usize, usize> = BTreeMap::new();
+  let mut record = |cheat: Cheat| *histogram.entry(cheat.saving).or_insert(0) += 1;
+
+  if let ([start], [end]) = (&starts[..], &ends[..])
+    && let Some(track) = map.trace_track(start, end)
+  {
+    let (from_start, to_end) = map.track_times(&track);
+    find_cheats(&track, &from_start, &to_end, Some(track.len() - 1), m).for_each(&mut record);
+    return histogram;
+  }
+
+  let to_ends: Vec<Distances> = ends.iter().map(|end| map.distances_from(end)).collect();
+  for start in starts.iter() {
+    let from_start = map.distances_from(start);
+    let track = map.reachable(&from_start);
+
+    for (end, to_end) in ends.iter().zip(to_ends.iter()) {
+      find_cheats(&track, &from_start, to_end, from_start[end.1][end.0], m).for_each(&mut record);
     }
   }
-  
-  possible_cheats
+
+  histogram
 }
 
-trait RacingMap {
-  fn neighbors(&self, coords: &Coords) -> Vec<Coords>;
-  fn coords_within_manhattan_distance(&self, coords: &Coords, distance: usize) -> Vec<Coords>;
-  fn find_possible_cheats(&self, coords: Coords, distance: usize) -> HashSet<Coords>;
-  fn find_best_path(&self, start: &Coords, end: &Coords) -> HashMap<Coords, usize>;
+pub fn find_best_cheats(input: &mut dyn BufRead, m: usize) -> Vec<Race> {
+  let map = CartesianGrid::from(read_input(input));
+  let starts = map.find_all_coords('S');
+  let ends = map.find_all_coords('E');
+
+  let from_starts: Vec<Distances> = starts.iter().map(|start| map.distances_from(start)).collect();
+  let to_ends: Vec<Distances> = ends.iter().map(|end| map.distances_from(end)).collect();
+
+  starts
+    .iter()
+    .zip(from_starts.iter())
+    .flat_map(|(start, from_start)| {
+      let track = map.reachable(from_start);
+      ends.iter().zip(to_ends.iter()).map(move |(end, to_end)| {
+        let time = from_start[end.1][end.0];
+        Race {
+          start: *start,
+          end: *end,
+          time,
+          best_cheat: find_cheats(&track, from_start, to_end, time, m).min_by_key(|cheat| Reverse(cheat.saving)),
+        }
+      })
+    })
+    .collect()
 }
 
-#[derive(Eq, PartialEq)]
-struct State {
-  position: Coords,
-  time: usize
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cheat {
+  pub start: Coords,
+  pub end: Coords,
+  pub saving: usize,
 }
 
-impl State {
-  fn new(position: Coords, time: usize) -> State {
-    State {
-      position,
-      time
-    }
-  }
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Race {
+  pub start: Coords,
+  pub end: Coords,
+  pub time: Option<usize>,
+  pub best_cheat: Option<Cheat>,
 }
 
-impl PartialOrd for State {
-  fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-    Some(self.cmp(other))
-  }
+type Distances = Vec<Vec<Option<usize>>>;
+
+fn find_cheats<'a>(
+  track: &'a [Coords],
+  from_start: &'a Distances,
+  to_end: &'a Distances,
+  best_time: Option<usize>,
+  m: usize,
+) -> impl Iterator<Item = Cheat> + 'a {
+  let m = m as isize;
+
+  track
+    .iter()
+    .filter(move |_| best_time.is_some())
+    .flat_map(move |&cheat_start| {
+      (-m..=m).flat_map(move |dy| (-(m - dy.abs())..=(m - dy.abs())).map(move |dx| (cheat_start, ICoords(dx, dy))))
+    })
+    .filter_map(move |(cheat_start, d)| {
+      let cheat_end = (cheat_start + d).to_coords()?;
+      let before = from_start[cheat_start.1][cheat_start.0]?;
+      let after = (*to_end.get(cheat_end.1)?.get(cheat_end.0)?)?;
+      let time = before + (d.0.abs() + d.1.abs()) as usize + after;
+
+      best_time
+        .filter(|&best| best > time)
+        .map(|best| Cheat {
+          start: cheat_start,
+          end: cheat_end,
+          saving: best - time,
+        })
+    })
 }
 
-impl Ord for State {
-  fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-    other.time.cmp(&self.time)
-  }
+trait RacingMap {
+  fn neighbors(&self, coords: &Coords) -> Vec<Coords>;
+  fn find_all_coords(&self, marker: char) -> Vec<Coords>;
+  fn trace_track(&self, start: &Coords, end: &Coords) -> Option<Vec<Coords>>;
+  fn track_times(&self, track: &[Coords]) -> (Distances, Distances);
+  fn distances_from(&self, source: &Coords) -> Distances;
+  fn reachable(&self, distances: &Distances) -> Vec<Coords>;
 }
 
 impl RacingMap for CartesianGrid<char> {
   fn neighbors(&self, coords: &Coords) -> Vec<Coords> {
-    self.coords_within_manhattan_distance(coords, 1)
+    [ICoords(0, -1), ICoords(1, 0), ICoords(0, 1), ICoords(-1, 0)]
       .iter()
-      .filter(|c| *self.get(c) != '#')
-      .map(|c| *c)
-      .collect_vec()
-  }
-
-  fn coords_within_manhattan_distance(&self, coords: &Coords, distance: usize) -> Vec<Coords> {
-    (-(distance as isize)..=distance as isize)
-      .cartesian_product(-(distance as isize)..=distance as isize)
-      .filter(|(dx, dy)| dx.abs() + dy.abs() <= distance as isize)
-      .filter(|(dx, dy)| !(*dx == 0 && *dy == 0))
-      .map(|(dx, dy)| coords + ICoords(dx, dy))
+      .map(|d| coords + d)
+      .filter(|c| self.in_grid(c))
       .flat_map(|c| c.to_coords())
-      .filter(|c| c.in_grid(self))
+      .filter(|c| *self.get(c) != '#')
       .collect()
   }
 
-  fn find_possible_cheats(&self, coords: Coords, distance: usize) -> HashSet<Coords> {
-    self.coords_within_manhattan_distance(&coords, distance)
-      .iter()
-      .filter(|c| *self.get(*c) != '#')
-      .map(|c| *c)
+  fn find_all_coords(&self, marker: char) -> Vec<Coords> {
+    self
+      .coords()
+      .into_iter()
+      .filter(|c| *self.get(c) == marker)
       .collect()
   }
 
-  fn find_best_path(&self, start: &Coords, end: &Coords) -> HashMap<Coords, usize> {
-    let mut visited: HashSet<Coords> = HashSet::new();
-    let mut dist: HashMap<Coords, usize> = HashMap::new();
-    let mut queue: BinaryHeap<State> = BinaryHeap::new();
-    queue.push(State::new(*start, 0));
-    dist.insert(*start, 0);
-    
-    while let Some(s) = queue.pop() {
-      if s.position == *end {
-        return dist
+  fn trace_track(&self, start: &Coords, end: &Coords) -> Option<Vec<Coords>> {
+    let mut track = vec![*start];
+
+    loop {
+      let current = track[track.len() - 1];
+      let previous = track.len().checked_sub(2).map(|i| track[i]);
+      let next: Vec<Coords> = self
+        .neighbors(&current)
+        .into_iter()
+        .filter(|n| Some(*n) != previous)
+        .collect();
+
+      match next[..] {
+        [] if current == *end => return Some(track),
+        [n] if current != *end => track.push(n),
+        _ => return None,
       }
+    }
+  }
+
+  fn track_times(&self, track: &[Coords]) -> (Distances, Distances) {
+    let mut from_start: Distances = self.grid.iter().map(|row| vec![None; row.len()]).collect();
+    let mut to_end = from_start.clone();
 
-      visited.insert(s.position);
+    for (time, c) in track.iter().enumerate() {
+      from_start[c.1][c.0] = Some(time);
+      to_end[c.1][c.0] = Some(track.len() - 1 - time);
+    }
+
+    (from_start, to_end)
+  }
 
-      for neighbor in self.neighbors(&s.position) {
-        let old = dist.get(&neighbor);
-        let alt = s.time + 1;
+  fn distances_from(&self, source: &Coords) -> Distances {
+    let mut distances: Distances = self.grid.iter().map(|row| vec![None; row.len()]).collect();
+    let mut queue: VecDeque<Coords> = VecDeque::from([*source]);
+    distances[source.1][source.0] = Some(0);
 
-        if !visited.contains(&neighbor) {
-          if old.is_none() || alt < *old.unwrap() {
-            dist.insert(neighbor, alt);
-            queue.push(State::new(neighbor, alt));
-          }
+    while let Some(current) = queue.pop_front() {
+      let distance = distances[current.1][current.0].unwrap();
+
+      for neighbor in self.neighbors(&current) {
+        if distances[neighbor.1][neighbor.0].is_none() {
+          distances[neighbor.1][neighbor.0] = Some(distance + 1);
+          queue.push_back(neighbor);
         }
       }
     }
 
-    panic!("No path found")
+    distances
+  }
+
+  fn reachable(&self, distances: &Distances) -> Vec<Coords> {
+    self
+      .coords()
+      .into_iter()
+      .filter(|c| distances[c.1][c.0].is_some())
+      .collect()
   }
 }
 
 #[cfg(test)]
 mod tests {
-  use crate::{day20::how_many_m_lasting_cheats_would_save_at_least_n_picoseconds, read};
+  use std::collections::BTreeMap;
+
+  use crate::{
+    day20::{cheat_savings_histogram, find_best_cheats, how_many_m_lasting_cheats_would_save_at_least_n_picoseconds, Cheat, Race, RacingMap},
+    read, read_input, CartesianGrid, Coords,
+  };
 
   #[test]
   fn sample_part1_input() {
@@ -181,4 +255,141 @@ mod tests {
   fn my_part2_input() {
     assert_eq!(how_many_m_lasting_cheats_would_save_at_least_n_picoseconds(&mut read("./src/day20/my.input"), 20, 100), 1015247)
   }
-}
\ No newline at end of file
+
+  #[test]
+  fn sample_part1_histogram() {
+    assert_eq!(
+      cheat_savings_histogram(&mut read("./src/day20/sample.input"), 2),
+      BTreeMap::from([
+        (2, 14),
+        (4, 14),
+        (6, 2),
+        (8, 4),
+        (10, 2),
+        (12, 3),
+        (20, 1),
+        (36, 1),
+        (38, 1),
+        (40, 1),
+        (64, 1)
+      ])
+    )
+  }
+
+  #[test]
+  fn sample_part2_histogram() {
+    let histogram = cheat_savings_histogram(&mut read("./src/day20/sample.input"), 20);
+
+    assert_eq!(
+      histogram.range(50..).map(|(&saving, &count)| (saving, count)).collect::<Vec<_>>(),
+      vec![
+        (50, 32),
+        (52, 31),
+        (54, 29),
+        (56, 39),
+        (58, 25),
+        (60, 23),
+        (62, 20),
+        (64, 19),
+        (66, 12),
+        (68, 14),
+        (70, 12),
+        (72, 22),
+        (74, 4),
+        (76, 3)
+      ]
+    )
+  }
+
+  #[test]
+  fn single_track_is_walked_and_branches_are_detected() {
+    let map = CartesianGrid::from(read_input(&mut read("./src/day20/sample.input")));
+    let track = map.trace_track(&map.find_one_coords('S').unwrap(), &map.find_one_coords('E').unwrap());
+    assert_eq!(track.map(|t| t.len()), Some(85));
+
+    let branched = CartesianGrid::from(read_input(&mut read("./src/day20/branched.sample.input")));
+    assert_eq!(branched.trace_track(&Coords::new(1, 1), &Coords::new(9, 1)), None);
+  }
+
+  #[test]
+  fn branched_sample_histogram() {
+    let histogram = cheat_savings_histogram(&mut read("./src/day20/branched.sample.input"), 2);
+    assert_eq!(histogram, BTreeMap::from([(2, 6), (4, 7), (8, 2), (12, 1), (14, 1), (16, 1)]));
+
+    let map = read_input(&mut read("./src/day20/branched.sample.input")).join("\n");
+    let mut per_pair: BTreeMap<usize, usize> = BTreeMap::new();
+    for (start, end) in [((1, 1), (9, 1)), ((1, 1), (1, 5)), ((9, 5), (9, 1)), ((9, 5), (1, 5))] {
+      let single: String = map
+        .lines()
+        .enumerate()
+        .map(|(y, line)| {
+          line
+            .chars()
+            .enumerate()
+            .map(|(x, c)| match c {
+              'S' if (x, y) != start => '.',
+              'E' if (x, y) != end => '.',
+              c => c,
+            })
+            .collect::<String>()
+            + "\n"
+        })
+        .collect();
+
+      for (saving, count) in cheat_savings_histogram(&mut single.as_bytes(), 2) {
+        *per_pair.entry(saving).or_insert(0) += count;
+      }
+    }
+    assert_eq!(histogram, per_pair);
+  }
+
+  #[test]
+  fn histogram_without_markers_is_empty() {
+    assert!(cheat_savings_histogram(&mut "#####\n#...#\n#####\n".as_bytes(), 2).is_empty());
+  }
+
+  #[test]
+  fn branched_sample_best_cheats() {
+    assert_eq!(
+      find_best_cheats(&mut read("./src/day20/branched.sample.input"), 2),
+      vec![
+        Race {
+          start: Coords::new(1, 1),
+          end: Coords::new(9, 1),
+          time: Some(12),
+          best_cheat: Some(Cheat {
+            start: Coords::new(5, 1),
+            end: Coords::new(7, 1),
+            saving: 4
+          })
+        },
+        Race {
+          start: Coords::new(1, 1),
+          end: Coords::new(1, 5),
+          time: Some(8),
+          best_cheat: Some(Cheat {
+            start: Coords::new(1, 3),
+            end: Coords::new(1, 5),
+            saving: 4
+          })
+        },
+        Race {
+          start: Coords::new(9, 5),
+          end: Coords::new(9, 1),
+          time: Some(4),
+          best_cheat: None
+        },
+        Race {
+          start: Coords::new(9, 5),
+          end: Coords::new(1, 5),
+          time: Some(24),
+          best_cheat: Some(Cheat {
+            start: Coords::new(7, 5),
+            end: Coords::new(5, 5),
+            saving: 16
+          })
+        },
+      ]
+    )
+  }
+}
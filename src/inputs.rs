@@ -0,0 +1,205 @@
+use std::{
+  env,
+  fs::{self, File},
+  io::{self, BufReader},
+  path::{Path, PathBuf},
+};
+
+use crate::samples::ANSWERS_FILE;
+
+pub const INPUT_ROOT_VAR: &str = "AOC_INPUT_ROOT";
+
+#[derive(Debug)]
+pub enum InputError {
+  NotFound(PathBuf),
+  Io(io::Error),
+  #[cfg(feature = "fetch")]
+  Fetch(String),
+}
+
+impl From<io::Error> for InputError {
+  fn from(error: io::Error) -> Self {
+    InputError::Io(error)
+  }
+}
+
+pub struct InputResolver {
+  root: PathBuf,
+  #[cfg(feature = "fetch")]
+  fetcher: Option<Fetcher>,
+}
+
+impl InputResolver {
+  pub fn new(root: impl Into<PathBuf>) -> Self {
+    InputResolver {
+      root: root.into(),
+      #[cfg(feature = "fetch")]
+      fetcher: None,
+    }
+  }
+
+  pub fn from_env() -> Self {
+    Self::new(env::var_os(INPUT_ROOT_VAR).map(PathBuf::from).unwrap_or_else(default_root))
+  }
+
+  #[cfg(feature = "fetch")]
+  pub fn with_fetcher(self, fetcher: Fetcher) -> Self {
+    InputResolver {
+      fetcher: Some(fetcher),
+      ..self
+    }
+  }
+
+  pub fn root(&self) -> &Path {
+    &self.root
+  }
+
+  pub fn path(&self, day: u32, variant: &str) -> PathBuf {
+    self.root.join(format!("day{:02}", day)).join(format!("{}.input", variant))
+  }
+
+  pub fn answers_path(&self, day: u32) -> PathBuf {
+    self.root.join(format!("day{:02}", day)).join(ANSWERS_FILE)
+  }
+
+  pub fn variants(&self, day: u32) -> Vec<String> {
+    let mut variants: Vec<String> = fs::read_dir(self.root.join(format!("day{:02}", day)))
+      .into_iter()
+      .flatten()
+      .flatten()
+      .filter_map(|entry| entry.file_name().to_str()?.strip_suffix(".input").map(String::from))
+      .collect();
+    variants.sort();
+    variants
+  }
+
+  pub fn open(&self, day: u32, variant: &str) -> Result<BufReader<File>, InputError> {
+    let path = self.path(day, variant);
+
+    if !path.exists() {
+      #[cfg(feature = "fetch")]
+      if let Some(fetcher) = self.fetcher.as_ref().filter(|_| variant == "my") {
+        fs::create_dir_all(path.parent().unwrap())?;
+        fs::write(&path, fetcher.fetch(day)?)?;
+        return Ok(BufReader::new(File::open(path)?));
+      }
+
+      return Err(InputError::NotFound(path));
+    }
+
+    Ok(BufReader::new(File::open(path)?))
+  }
+}
+
+pub fn open(day: u32, variant: &str) -> BufReader<File> {
+  InputResolver::from_env().open(day, variant).unwrap()
+}
+
+fn default_root() -> PathBuf {
+  Path::new(env!("CARGO_MANIFEST_DIR")).join("src")
+}
+
+#[cfg(feature = "fetch")]
+pub const SESSION_VAR: &str = "AOC_SESSION";
+
+#[cfg(feature = "fetch")]
+pub struct Fetcher {
+  base_url: String,
+  session: String,
+}
+
+#[cfg(feature = "fetch")]
+impl Fetcher {
+  pub fn new(base_url: &str, session: &str) -> Self {
+    Fetcher {
+      base_url: base_url.trim_end_matches('/').to_string(),
+      session: session.to_string(),
+    }
+  }
+
+  pub fn from_env() -> Option<Self> {
+    env::var(SESSION_VAR)
+      .ok()
+      .map(|session| Self::new("https://adventofcode.com/2024", &session))
+  }
+
+  pub fn fetch(&self, day: u32) -> Result<String, InputError> {
+    ureq::get(&format!("{}/day/{}/input", self.base_url, day))
+      .set("Cookie", &format!("session={}", self.session))
+      .call()
+      .map_err(|e| InputError::Fetch(e.to_string()))?
+      .into_string()
+      .map_err(InputError::Io)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{env, fs, io::Read, path::PathBuf};
+
+  use crate::{
+    day06::count_positions_visited_by_guard,
+    inputs::{InputError, InputResolver},
+  };
+
+  fn temp_root(name: &str) -> PathBuf {
+    let root = env::temp_dir().join(format!("aoc2024-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    root
+  }
+
+  #[test]
+  fn default_root_resolves_committed_inputs() {
+    let resolver = InputResolver::new(env!("CARGO_MANIFEST_DIR").to_string() + "/src");
+    assert_eq!(count_positions_visited_by_guard(&mut resolver.open(6, "my").unwrap()), 5444);
+    assert_eq!(resolver.variants(15), vec!["larger.sample", "my", "scaled.sample", "smaller.part2.sample", "smaller.sample"]);
+  }
+
+  #[test]
+  fn custom_root_with_variants() {
+    let root = temp_root("variants");
+    fs::create_dir_all(root.join("day03")).unwrap();
+    fs::write(root.join("day03/sample2.part1.input"), "mul(2,4)").unwrap();
+
+    let resolver = InputResolver::new(&root);
+    let mut contents = String::new();
+    resolver.open(3, "sample2.part1").unwrap().read_to_string(&mut contents).unwrap();
+
+    assert_eq!(contents, "mul(2,4)");
+    assert!(matches!(resolver.open(3, "my"), Err(InputError::NotFound(path)) if path == root.join("day03/my.input")));
+    fs::remove_dir_all(root).unwrap();
+  }
+
+  #[cfg(feature = "fetch")]
+  #[test]
+  fn missing_input_is_fetched_once_and_cached() {
+    use crate::inputs::Fetcher;
+
+    let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+    let address = server.server_addr().to_ip().unwrap();
+    let handle = std::thread::spawn(move || {
+      let request = server.recv().unwrap();
+      let cookie = request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv("Cookie"))
+        .map(|h| h.value.to_string());
+      let url = request.url().to_string();
+      request.respond(tiny_http::Response::from_string("3   4\n4   3\n")).unwrap();
+      (url, cookie)
+    });
+
+    let root = temp_root("fetch");
+    let resolver = InputResolver::new(&root).with_fetcher(Fetcher::new(&format!("http://{}/2024/", address), "secret"));
+
+    let mut contents = String::new();
+    resolver.open(1, "my").unwrap().read_to_string(&mut contents).unwrap();
+    assert_eq!(contents, "3   4\n4   3\n");
+    assert_eq!(handle.join().unwrap(), ("/2024/day/1/input".to_string(), Some("session=secret".to_string())));
+
+    assert_eq!(fs::read_to_string(root.join("day01/my.input")).unwrap(), contents);
+    assert!(resolver.open(1, "my").is_ok());
+    assert!(matches!(resolver.open(1, "sample"), Err(InputError::NotFound(_))));
+    fs::remove_dir_all(root).unwrap();
+  }
+}
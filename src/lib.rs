@@ -2,10 +2,9 @@ use std::{
   fs::File,
   io::{BufRead, BufReader},
   ops::{self},
+  path::Path,
 };
 
-use itertools::Itertools;
-
 pub mod day01;
 pub mod day02;
 pub mod day03;
@@ -26,6 +25,9 @@ pub mod day17;
 pub mod day18;
 pub mod day19;
 pub mod day20;
+pub mod inputs;
+pub mod samples;
+pub mod scaffold;
 
 pub fn read_input(input: &mut dyn BufRead) -> Vec<String> {
   input
@@ -35,7 +37,12 @@ pub fn read_input(input: &mut dyn BufRead) -> Vec<String> {
 }
 
 pub fn read(file_name: &str) -> BufReader<File> {
-  BufReader::new(File::open(file_name).unwrap())
+  let path = Path::new(file_name);
+  if path.exists() {
+    BufReader::new(File::open(path).unwrap())
+  } else {
+    BufReader::new(File::open(Path::new(env!("CARGO_MANIFEST_DIR")).join(path)).unwrap())
+  }
 }
 
 #[derive(Clone)]
@@ -271,13 +278,6 @@ impl<T: std::fmt::Display + std::cmp::PartialEq> CartesianGrid<T> {
       && coord.0 < self.grid.get(coord.1 as usize).unwrap().len() as isize
   }
 
-  fn is_boundary(&self, coord: &Coords) -> bool {
-    coord.1 == 0
-      || coord.1 == self.grid.len() - 1
-      || coord.0 == 0
-      || coord.0 == self.grid.get(coord.1 as usize).unwrap().len() - 1
-  }
-
   fn find_one_coords(&self, value: T) -> Option<Coords> {
     self
       .coords()
@@ -290,32 +290,6 @@ impl<T: std::fmt::Display + std::cmp::PartialEq> CartesianGrid<T> {
     self.grid.get_mut(coord.1).unwrap()[coord.0] = value
   }
 
-  fn get_coords_between(&self, from: &Coords, to: &Coords) -> Vec<Coords> {
-    let dx = from.0 as isize - to.0 as isize;
-    let dy = from.1 as isize - to.1 as isize;
-
-    if dx == 0 {
-      if dy < 0 {
-        (from.1..to.1).map(|y| Coords(from.1, y)).collect_vec()
-      }
-      else {
-        (to.1 + 1..=from.1).map(|y| Coords(from.1, y)).rev().collect_vec()
-      }
-    }
-    else if dy == 0 {
-      if dx < 0 {
-        (from.0..to.0).map(|x| Coords(x, from.1)).collect_vec()  
-      }
-      else {
-        (to.0 + 1..=from.0).map(|x| Coords(x, from.1)).rev().collect_vec()
-      }
-    }
-    else {
-      // TODO: diagonals
-      vec![]
-    }
-  }
-
   #[allow(dead_code)]
   fn print(&self) {
     for level in self.grid.iter() {
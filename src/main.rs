@@ -1,3 +1,158 @@
-fn main() {
-    println!("Advent of Code 2024");
+use std::{env, path::Path, process::ExitCode};
+
+use day01::{
+  day05,
+  inputs::{InputError, InputResolver, INPUT_ROOT_VAR},
+  read, samples, scaffold,
+};
+
+fn main() -> ExitCode {
+  let args: Vec<String> = env::args().skip(1).collect();
+  let explain = args.iter().any(|arg| arg == "--explain");
+  let force = args.iter().any(|arg| arg == "--force");
+  let input_root = args.iter().position(|arg| arg == "--input-root").and_then(|i| args.get(i + 1));
+  let positional: Vec<&str> = args
+    .iter()
+    .enumerate()
+    .filter(|(i, arg)| !arg.starts_with("--") && (*i == 0 || args[i - 1] != "--input-root"))
+    .map(|(_, arg)| arg.as_str())
+    .collect();
+
+  let resolver = match input_root {
+    Some(root) => InputResolver::new(root),
+    None => resolver_from_env(),
+  };
+
+  if let ["extract-samples", day, html] = positional[..] {
+    return match (day.parse::<u32>(), std::fs::read_to_string(html)) {
+      (Ok(day), Ok(html)) => extract_samples(&resolver, day, &html, force),
+      (Err(_), _) => usage(),
+      (_, Err(error)) => {
+        eprintln!("cannot read {}: {}", html, error);
+        ExitCode::FAILURE
+      }
+    };
+  }
+
+  if let ["new-day", day] = positional[..] {
+    return match day.parse::<u32>() {
+      Ok(day) => new_day(input_root, day),
+      Err(_) => usage(),
+    };
+  }
+
+  let day = match positional.first().map(|day| day.parse::<u32>()) {
+    None => {
+      println!("Advent of Code 2024");
+      return ExitCode::SUCCESS;
+    }
+    Some(Ok(day)) => day,
+    Some(Err(_)) => return usage(),
+  };
+  let input = positional.get(1).copied().unwrap_or("my");
+
+  let open = || {
+    if Path::new(input).is_file() {
+      Ok(read(input))
+    } else {
+      resolver.open(day, input)
+    }
+  };
+
+  let result = match day {
+    5 if explain => open().map(|mut f| print!("{}", day05::explain_incorrect_updates(&mut f))),
+    5 => open().and_then(|mut f| {
+      println!("{}", day05::sum_middle_page_numbers_of_correct_updates(&mut f));
+      open().map(|mut f| match day05::sum_middle_page_numbers_of_corrected_updates(&mut f) {
+        Ok(sum) => println!("{}", sum),
+        Err(error) => eprintln!("cannot correct updates: {:?}", error),
+      })
+    }),
+    _ => return usage(),
+  };
+
+  match result {
+    Ok(()) => ExitCode::SUCCESS,
+    Err(InputError::NotFound(path)) => {
+      eprintln!("input not found: {}", path.display());
+      ExitCode::FAILURE
+    }
+    Err(error) => {
+      eprintln!("cannot read input: {:?}", error);
+      ExitCode::FAILURE
+    }
+  }
+}
+
+#[cfg(feature = "fetch")]
+fn resolver_from_env() -> InputResolver {
+  match day01::inputs::Fetcher::from_env() {
+    Some(fetcher) => InputResolver::from_env().with_fetcher(fetcher),
+    None => InputResolver::from_env(),
+  }
+}
+
+#[cfg(not(feature = "fetch"))]
+fn resolver_from_env() -> InputResolver {
+  InputResolver::from_env()
+}
+
+fn extract_samples(resolver: &InputResolver, day: u32, html: &str, force: bool) -> ExitCode {
+  let puzzle = samples::extract_samples(html);
+
+  match samples::write_samples(resolver, day, &puzzle, force) {
+    Ok(written) => {
+      for sample in puzzle.samples.iter() {
+        let status = if written.contains(&sample.variant) { "written" } else { "kept" };
+        println!("{} {}", status, resolver.path(day, &sample.variant).display());
+      }
+      for answer in puzzle.answers.iter() {
+        println!("answer {} part{} {}", answer.variant, answer.part, answer.value);
+      }
+      ExitCode::SUCCESS
+    }
+    Err(error) => {
+      eprintln!("cannot write samples: {}", error);
+      ExitCode::FAILURE
+    }
+  }
+}
+
+fn new_day(input_root: Option<&String>, day: u32) -> ExitCode {
+  let crate_root = match env::current_dir() {
+    Ok(dir) => dir,
+    Err(error) => {
+      eprintln!("cannot determine working directory: {}", error);
+      return ExitCode::FAILURE;
+    }
+  };
+
+  let resolver = match input_root {
+    Some(root) => InputResolver::new(root),
+    None if env::var_os(INPUT_ROOT_VAR).is_some() => InputResolver::from_env(),
+    None => InputResolver::new(crate_root.join("src")),
+  };
+
+  match scaffold::new_day(&crate_root, &resolver, day) {
+    Ok(scaffold) => {
+      for path in scaffold.created.iter() {
+        println!("created {}", path.display());
+      }
+      for path in scaffold.updated.iter() {
+        println!("updated {}", path.display());
+      }
+      ExitCode::SUCCESS
+    }
+    Err(error) => {
+      eprintln!("cannot scaffold day {}: {}", day, error);
+      ExitCode::FAILURE
+    }
+  }
+}
+
+fn usage() -> ExitCode {
+  eprintln!("usage: day01 <day> [variant | input file] [--input-root <dir>] [--explain]");
+  eprintln!("       day01 extract-samples <day> <puzzle html> [--input-root <dir>] [--force]");
+  eprintln!("       day01 new-day <day> [--input-root <dir>]");
+  ExitCode::FAILURE
 }
@@ -0,0 +1,266 @@
+use std::{fs, io, path::Path};
+
+use itertools::Itertools;
+use regex::Regex;
+
+use crate::inputs::InputResolver;
+
+pub const ANSWERS_FILE: &str = "answers";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sample {
+  pub variant: String,
+  pub input: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Answer {
+  pub variant: String,
+  pub part: u32,
+  pub value: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PuzzleSamples {
+  pub samples: Vec<Sample>,
+  pub answers: Vec<Answer>,
+}
+
+pub fn extract_samples(html: &str) -> PuzzleSamples {
+  let article = Regex::new(r"(?s)<article[^>]*>(.*?)</article>").unwrap();
+  let pre = Regex::new(r"(?s)<pre><code>(.*?)</code></pre>").unwrap();
+  let emphasized = Regex::new(r"(?s)<code><em>(.*?)</em></code>|<em><code>(.*?)</code></em>").unwrap();
+
+  let mut blocks: Vec<(u32, String)> = Vec::new();
+  let mut answers: Vec<(u32, Option<usize>, String)> = Vec::new();
+
+  for (description, part) in article.captures_iter(html).map(|c| c.get(1).unwrap().as_str()).zip(1..) {
+    for block in pre.captures_iter(description) {
+      let input = strip_tags(block.get(1).unwrap().as_str());
+      if !blocks.iter().any(|(_, b)| *b == input) {
+        blocks.push((part, input));
+      }
+    }
+
+    let answer = emphasized
+      .captures_iter(description)
+      .last()
+      .map(|c| strip_tags(c.get(1).or(c.get(2)).unwrap().as_str()));
+
+    if let Some(value) = answer {
+      let last_block = pre
+        .captures_iter(description)
+        .last()
+        .map(|c| strip_tags(c.get(1).unwrap().as_str()))
+        .and_then(|input| blocks.iter().position(|(_, b)| *b == input))
+        .or(blocks.len().checked_sub(1));
+      answers.push((part, last_block, value));
+    }
+  }
+
+  let variant = |index: usize, part: u32| match (blocks.len(), part) {
+    (1, 1) => "sample".to_string(),
+    (1, _) => format!("sample.part{}", part),
+    (_, 1) => format!("sample{}", index + 1),
+    _ => format!("sample{}.part{}", index + 1, part),
+  };
+
+  let mut puzzle = PuzzleSamples::default();
+  for (index, (part, input)) in blocks.iter().enumerate() {
+    puzzle.samples.push(Sample { variant: variant(index, *part), input: input.clone() });
+  }
+  for (part, block, value) in answers {
+    if let Some(index) = block {
+      puzzle.answers.push(Answer { variant: puzzle.samples[index].variant.clone(), part, value });
+    }
+  }
+
+  puzzle
+}
+
+pub fn write_samples(resolver: &InputResolver, day: u32, puzzle: &PuzzleSamples, force: bool) -> io::Result<Vec<String>> {
+  let mut written: Vec<String> = Vec::new();
+
+  for sample in puzzle.samples.iter() {
+    let path = resolver.path(day, &sample.variant);
+    if force || !path.exists() {
+      fs::create_dir_all(path.parent().unwrap())?;
+      fs::write(&path, &sample.input)?;
+      written.push(sample.variant.clone());
+    }
+  }
+
+  let manifest_path = resolver.answers_path(day);
+  let mut manifest = AnswersManifest::load(&manifest_path)?;
+  for answer in puzzle.answers.iter().filter(|a| written.contains(&a.variant)) {
+    manifest.set(answer.clone());
+  }
+  fs::write(&manifest_path, manifest.to_string())?;
+
+  Ok(written)
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AnswersManifest {
+  answers: Vec<Answer>,
+}
+
+impl AnswersManifest {
+  pub fn parse(contents: &str) -> Self {
+    let answers = contents
+      .lines()
+      .map(str::trim)
+      .filter(|line| !line.is_empty() && !line.starts_with('#'))
+      .flat_map(|line| {
+        let (variant, part, value) = line.splitn(3, ' ').collect_tuple()?;
+        Some(Answer {
+          variant: variant.to_string(),
+          part: part.strip_prefix("part")?.parse().ok()?,
+          value: value.trim().to_string(),
+        })
+      })
+      .collect();
+
+    AnswersManifest { answers }
+  }
+
+  pub fn load(path: &Path) -> io::Result<Self> {
+    match fs::read_to_string(path) {
+      Ok(contents) => Ok(Self::parse(&contents)),
+      Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+      Err(e) => Err(e),
+    }
+  }
+
+  pub fn get(&self, variant: &str, part: u32) -> Option<&str> {
+    self
+      .answers
+      .iter()
+      .find(|a| a.variant == variant && a.part == part)
+      .map(|a| a.value.as_str())
+  }
+
+  pub fn set(&mut self, answer: Answer) {
+    match self.answers.iter_mut().find(|a| a.variant == answer.variant && a.part == answer.part) {
+      Some(existing) => existing.value = answer.value,
+      None => self.answers.push(answer),
+    }
+  }
+}
+
+impl std::fmt::Display for AnswersManifest {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    for a in self.answers.iter().sorted_by(|a, b| (&a.variant, a.part).cmp(&(&b.variant, b.part))) {
+      writeln!(f, "{} part{} {}", a.variant, a.part, a.value)?;
+    }
+    Ok(())
+  }
+}
+
+fn strip_tags(html: &str) -> String {
+  let tag = Regex::new(r"<[^>]*>").unwrap();
+  tag
+    .replace_all(html, "")
+    .replace("&lt;", "<")
+    .replace("&gt;", ">")
+    .replace("&quot;", "\"")
+    .replace("&#39;", "'")
+    .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{env, fs};
+
+  use crate::{
+    inputs::InputResolver,
+    samples::{extract_samples, write_samples, Answer, AnswersManifest},
+  };
+
+  const PUZZLE: &str = r#"<main>
+<article class="day-desc"><h2>--- Day 5: Print Queue ---</h2>
+<p>For example:</p>
+<pre><code>47|53
+97|13
+
+75,47,61,53,29
+</code></pre>
+<p>The middle page is <code>61</code>; adding these together produces <code><em>143</em></code>.</p>
+</article>
+<p>Your puzzle answer was <code>6034</code>.</p>
+<article class="day-desc"><h2 id="part2">--- Part Two ---</h2>
+<p>Illustration <pre><code>a -&gt; <em>b</em> &amp; c
+</code></pre> and a larger example:</p>
+<pre><code>x
+y
+</code></pre>
+<p>It has <em><code>123</code></em> in total.</p>
+</article>
+</main>"#;
+
+  #[test]
+  fn samples_and_answers_are_extracted() {
+    let puzzle = extract_samples(PUZZLE);
+
+    assert_eq!(puzzle.samples.len(), 3);
+    assert_eq!(puzzle.samples[0].variant, "sample1");
+    assert_eq!(puzzle.samples[0].input, "47|53\n97|13\n\n75,47,61,53,29\n");
+    assert_eq!(puzzle.samples[1].variant, "sample2.part2");
+    assert_eq!(puzzle.samples[1].input, "a -> b & c\n");
+    assert_eq!(puzzle.samples[2].variant, "sample3.part2");
+    assert_eq!(puzzle.samples[2].input, "x\ny\n");
+    assert_eq!(
+      puzzle.answers,
+      vec![
+        Answer { variant: "sample1".to_string(), part: 1, value: "143".to_string() },
+        Answer { variant: "sample3.part2".to_string(), part: 2, value: "123".to_string() },
+      ]
+    );
+  }
+
+  #[test]
+  fn repeated_sample_reuses_variant() {
+    let html = PUZZLE
+      .replace("a -&gt; <em>b</em> &amp; c\n", "47|53\n97|13\n\n75,47,61,53,29\n")
+      .replace("x\ny\n", "47|53\n97|13\n\n75,47,61,53,29\n");
+    let puzzle = extract_samples(&html);
+
+    assert_eq!(puzzle.samples.len(), 1);
+    assert_eq!(puzzle.samples[0].variant, "sample");
+    assert_eq!(puzzle.answers[1].variant, "sample");
+  }
+
+  #[test]
+  fn samples_are_written_with_merged_manifest() {
+    let root = env::temp_dir().join(format!("aoc2024-samples-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(root.join("day05")).unwrap();
+    fs::write(root.join("day05/answers"), "my part1 6034\nsample1 part1 1\n").unwrap();
+    fs::write(root.join("day05/sample1.input"), "kept").unwrap();
+
+    let resolver = InputResolver::new(&root);
+    let written = write_samples(&resolver, 5, &extract_samples(PUZZLE), false).unwrap();
+
+    assert_eq!(written, vec!["sample2.part2", "sample3.part2"]);
+    assert_eq!(fs::read_to_string(root.join("day05/sample1.input")).unwrap(), "kept");
+    assert_eq!(fs::read_to_string(root.join("day05/sample2.part2.input")).unwrap(), "a -> b & c\n");
+    assert_eq!(fs::read_to_string(root.join("day05/sample3.part2.input")).unwrap(), "x\ny\n");
+
+    let manifest = AnswersManifest::parse(&fs::read_to_string(root.join("day05/answers")).unwrap());
+    assert_eq!(manifest.get("my", 1), Some("6034"));
+    assert_eq!(manifest.get("sample1", 1), Some("1"));
+    assert_eq!(manifest.get("sample3.part2", 2), Some("123"));
+    assert_eq!(
+      manifest.to_string(),
+      "my part1 6034\nsample1 part1 1\nsample3.part2 part2 123\n"
+    );
+
+    write_samples(&resolver, 5, &extract_samples(PUZZLE), true).unwrap();
+    assert_eq!(fs::read_to_string(root.join("day05/sample1.input")).unwrap(), "47|53\n97|13\n\n75,47,61,53,29\n");
+    assert_eq!(
+      AnswersManifest::load(&root.join("day05/answers")).unwrap().get("sample1", 1),
+      Some("143")
+    );
+    fs::remove_dir_all(root).unwrap();
+  }
+}
@@ -0,0 +1,194 @@
+use std::{
+  fs, io,
+  path::{Path, PathBuf},
+};
+
+use crate::inputs::InputResolver;
+
+const MODULE_TEMPLATE: &str = r#"use std::io::BufRead;
+
+use crate::read_input;
+
+pub fn part1(input: &mut dyn BufRead) -> usize {
+  let _lines = read_input(input);
+  0
+}
+
+pub fn part2(input: &mut dyn BufRead) -> usize {
+  let _lines = read_input(input);
+  0
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::{dayNN::{part1, part2}, inputs::open};
+
+  #[test]
+  fn sample_part1_input() {
+    assert_eq!(part1(&mut open(DAY, "sample")), 0)
+  }
+
+  #[test]
+  fn my_part1_input() {
+    assert_eq!(part1(&mut open(DAY, "my")), 0)
+  }
+
+  #[test]
+  fn sample_part2_input() {
+    assert_eq!(part2(&mut open(DAY, "sample")), 0)
+  }
+
+  #[test]
+  fn my_part2_input() {
+    assert_eq!(part2(&mut open(DAY, "my")), 0)
+  }
+}
+"#;
+
+const DISPATCH_FALLBACK: &str = "    _ => return usage(),";
+
+const DISPATCH_TEMPLATE: &str = "    DAY => open().and_then(|mut f| {
+      println!(\"{}\", day01::dayNN::part1(&mut f));
+      open().map(|mut f| println!(\"{}\", day01::dayNN::part2(&mut f)))
+    }),
+";
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Scaffold {
+  pub created: Vec<PathBuf>,
+  pub updated: Vec<PathBuf>,
+}
+
+pub fn new_day(crate_root: &Path, resolver: &InputResolver, day: u32) -> io::Result<Scaffold> {
+  let module = format!("day{:02}", day);
+  let module_file = crate_root.join("src").join(&module).join("mod.rs");
+
+  if module_file.exists() {
+    return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} already exists", module)));
+  }
+
+  let mut scaffold = Scaffold::default();
+  let files = [
+    (module_file, MODULE_TEMPLATE.replace("dayNN", &module).replace("DAY", &day.to_string())),
+    (resolver.path(day, "my"), String::new()),
+    (resolver.path(day, "sample"), String::new()),
+    (resolver.answers_path(day), String::new()),
+  ];
+  for (path, contents) in files {
+    if !path.exists() {
+      fs::create_dir_all(path.parent().unwrap())?;
+      fs::write(&path, contents)?;
+      scaffold.created.push(path);
+    }
+  }
+
+  let lib = crate_root.join("src/lib.rs");
+  if register_module(&lib, &module)? {
+    scaffold.updated.push(lib);
+  }
+
+  let main = crate_root.join("src/main.rs");
+  if register_solver(&main, day, &module)? {
+    scaffold.updated.push(main);
+  }
+
+  Ok(scaffold)
+}
+
+fn register_module(lib: &Path, module: &str) -> io::Result<bool> {
+  let contents = fs::read_to_string(lib)?;
+  let declaration = format!("pub mod {};", module);
+  let lines: Vec<&str> = contents.lines().collect();
+
+  if lines.contains(&declaration.as_str()) {
+    return Ok(false);
+  }
+
+  let position = match lines.iter().rposition(|line| line.starts_with("pub mod day") && *line < declaration.as_str()) {
+    Some(i) => i + 1,
+    None => lines.iter().position(|line| line.starts_with("pub mod ")).unwrap_or(lines.len()),
+  };
+
+  let mut updated: Vec<&str> = lines[..position].to_vec();
+  updated.push(&declaration);
+  updated.extend_from_slice(&lines[position..]);
+  fs::write(lib, updated.join("\n") + "\n")?;
+
+  Ok(true)
+}
+
+fn register_solver(main: &Path, day: u32, module: &str) -> io::Result<bool> {
+  let contents = fs::read_to_string(main)?;
+
+  if contents.contains(&format!("day01::{}::", module)) {
+    return Ok(false);
+  }
+
+  let Some(position) = contents.find(DISPATCH_FALLBACK) else {
+    return Err(io::Error::new(io::ErrorKind::InvalidData, "solver dispatch not found in main.rs"));
+  };
+
+  let arm = DISPATCH_TEMPLATE.replace("DAY", &day.to_string()).replace("dayNN", module);
+  fs::write(main, format!("{}{}{}", &contents[..position], arm, &contents[position..]))?;
+
+  Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{env, fs, io};
+
+  use crate::{inputs::InputResolver, scaffold::new_day};
+
+  const LIB: &str = "use std::io::BufRead;\n\npub mod day01;\npub mod day02;\npub mod day20;\npub mod inputs;\n";
+  const MAIN: &str = "fn main() {\n  let result = match day {\n    5 => open(),\n    _ => return usage(),\n  };\n}\n";
+
+  #[test]
+  fn new_day_is_scaffolded_and_registered() {
+    let root = env::temp_dir().join(format!("aoc2024-scaffold-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::write(root.join("src/lib.rs"), LIB).unwrap();
+    fs::write(root.join("src/main.rs"), MAIN).unwrap();
+
+    let resolver = InputResolver::new(root.join("inputs"));
+    let scaffold = new_day(&root, &resolver, 3).unwrap();
+    assert_eq!(
+      scaffold.created,
+      vec![
+        root.join("src/day03/mod.rs"),
+        root.join("inputs/day03/my.input"),
+        root.join("inputs/day03/sample.input"),
+        root.join("inputs/day03/answers"),
+      ]
+    );
+    assert_eq!(scaffold.updated, vec![root.join("src/lib.rs"), root.join("src/main.rs")]);
+
+    let module = fs::read_to_string(root.join("src/day03/mod.rs")).unwrap();
+    assert!(module.contains("use crate::read_input;"));
+    assert!(module.contains("use crate::{day03::{part1, part2}, inputs::open};"));
+    assert!(module.contains("assert_eq!(part1(&mut open(3, \"my\")), 0)"));
+    assert!(!module.contains("./src"));
+    assert_eq!(module.matches("#[test]").count(), 4);
+
+    assert_eq!(fs::read_to_string(root.join("inputs/day03/sample.input")).unwrap(), "");
+    assert_eq!(fs::read_to_string(root.join("inputs/day03/answers")).unwrap(), "");
+    assert!(!root.join("src/day03/my.input").exists());
+    assert_eq!(
+      fs::read_to_string(root.join("src/lib.rs")).unwrap(),
+      "use std::io::BufRead;\n\npub mod day01;\npub mod day02;\npub mod day03;\npub mod day20;\npub mod inputs;\n"
+    );
+
+    let main = fs::read_to_string(root.join("src/main.rs")).unwrap();
+    assert!(main.contains("    3 => open().and_then(|mut f| {\n      println!(\"{}\", day01::day03::part1(&mut f));"));
+    assert!(main.find("day01::day03::part2").unwrap() < main.find("_ => return usage(),").unwrap());
+
+    assert_eq!(new_day(&root, &resolver, 3).unwrap_err().kind(), io::ErrorKind::AlreadyExists);
+
+    fs::remove_file(root.join("src/day03/mod.rs")).unwrap();
+    let scaffold = new_day(&root, &resolver, 3).unwrap();
+    assert_eq!(scaffold.created, vec![root.join("src/day03/mod.rs")]);
+    assert!(scaffold.updated.is_empty());
+    fs::remove_dir_all(root).unwrap();
+  }
+}